@@ -4,8 +4,23 @@
 
 pub mod prelude;
 
+pub mod audit_log;
+pub mod comment;
+pub mod mfa_recovery_code;
+pub mod password;
+pub mod permission;
+pub mod personal_access_token;
 pub mod post;
+pub mod post_reaction;
+pub mod post_tag;
+pub mod role;
+pub mod role_permission;
 pub mod sea_orm_active_enums;
+pub mod soft_delete;
+pub mod tag;
 pub mod user;
+pub mod user_follow;
+pub mod user_identity;
+pub mod user_role;
 
 mod serde_time;
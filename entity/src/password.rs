@@ -0,0 +1,97 @@
+//! Password hashing used by `user::ActiveModel::before_save`
+//!
+//! New hashes use Argon2id with the parameters passed to `configure`. Hashes are told apart by
+//! their PHC prefix, so passwords stored with bcrypt keep working until they are rehashed.
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use std::sync::OnceLock;
+
+static PARAMS: OnceLock<Params> = OnceLock::new();
+
+/// Set the Argon2id cost parameters, only the first call has an effect.
+/// The defaults of the argon2 crate are used when never called.
+pub fn configure(memory_kib: u32, iterations: u32, parallelism: u32) -> Result<(), argon2::Error> {
+    let params = Params::new(memory_kib, iterations, parallelism, None)?;
+    let _ = PARAMS.set(params);
+    Ok(())
+}
+
+fn argon2() -> Argon2<'static> {
+    let params = PARAMS.get().cloned().unwrap_or_default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+fn is_bcrypt(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"]
+        .iter()
+        .any(|prefix| hash.starts_with(prefix))
+}
+
+pub fn hash(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(argon2()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+pub fn verify(password: &str, hash: &str) -> bool {
+    if is_bcrypt(hash) {
+        return bcrypt::verify(password, hash).unwrap_or(false);
+    }
+
+    // the algorithm and parameters are read from the hash itself
+    PasswordHash::new(hash)
+        .map(|parsed| {
+            argon2()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+/// Whether the hash uses another algorithm or other parameters than new hashes
+pub fn needs_rehash(hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return true;
+    };
+    if parsed.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+
+    let current = PARAMS.get().cloned().unwrap_or_default();
+    match Params::try_from(&parsed) {
+        Ok(params) => {
+            params.m_cost() != current.m_cost()
+                || params.t_cost() != current.t_cost()
+                || params.p_cost() != current.p_cost()
+        }
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_argon2_hash() {
+        let hash = hash("password").unwrap();
+
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify("password", &hash));
+        assert!(!verify("wrong password", &hash));
+        assert!(!needs_rehash(&hash));
+    }
+
+    #[test]
+    fn test_bcrypt_fallback() {
+        let hash = bcrypt::hash("password", 4).unwrap();
+
+        assert!(verify("password", &hash));
+        assert!(!verify("wrong password", &hash));
+        assert!(needs_rehash(&hash));
+    }
+}
@@ -1,6 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.10
 
-use super::sea_orm_active_enums::Category;
+use super::sea_orm_active_enums::{Category, PostStatus, Visibility};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -14,13 +14,30 @@ pub struct Model {
     pub title: String,
     pub text: String,
     pub category: Option<Category>,
+    pub visibility: Visibility,
+    pub status: PostStatus,
+    /// when the post went out, or is going out once scheduled
+    #[serde(with = "super::serde_time")]
+    pub published_at: Option<DateTimeUtc>,
     #[serde(with = "super::serde_time")]
     pub created_at: Option<DateTimeUtc>,
     #[serde(with = "super::serde_time")]
     pub updated_at: Option<DateTimeUtc>,
+    #[serde(with = "super::serde_time")]
+    pub deleted_at: Option<DateTimeUtc>,
     pub user_id: i32,
     #[sea_orm(belongs_to, from = "user_id", to = "id")]
     pub user: HasOne<super::user::Entity>,
+    #[sea_orm(has_many)]
+    pub post_tags: HasMany<super::post_tag::Entity>,
+    #[sea_orm(has_many)]
+    pub comments: HasMany<super::comment::Entity>,
+    #[sea_orm(has_many)]
+    pub post_reactions: HasMany<super::post_reaction::Entity>,
+}
+
+impl super::soft_delete::SoftDelete for Entity {
+    const DELETED_AT: Column = Column::DeletedAt;
 }
 
 #[async_trait::async_trait]
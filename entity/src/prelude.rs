@@ -1,4 +1,17 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.10
 
+pub use super::audit_log::Entity as AuditLog;
+pub use super::comment::Entity as Comment;
+pub use super::mfa_recovery_code::Entity as MfaRecoveryCode;
+pub use super::permission::Entity as Permission;
+pub use super::personal_access_token::Entity as PersonalAccessToken;
 pub use super::post::Entity as Post;
+pub use super::post_reaction::Entity as PostReaction;
+pub use super::post_tag::Entity as PostTag;
+pub use super::role::Entity as Role;
+pub use super::role_permission::Entity as RolePermission;
+pub use super::tag::Entity as Tag;
 pub use super::user::Entity as User;
+pub use super::user_follow::Entity as UserFollow;
+pub use super::user_identity::Entity as UserIdentity;
+pub use super::user_role::Entity as UserRole;
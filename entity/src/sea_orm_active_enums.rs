@@ -11,3 +11,75 @@ pub enum Category {
     #[sea_orm(string_value = "Story")]
     Story,
 }
+
+/// Who can read a post
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "visibility")]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// only the author
+    #[default]
+    #[sea_orm(string_value = "private")]
+    Private,
+    /// anyone with the link, left out of the feed
+    #[sea_orm(string_value = "unlisted")]
+    Unlisted,
+    /// anyone, listed in the feed
+    #[sea_orm(string_value = "public")]
+    Public,
+}
+
+/// How a user reacted to a post
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumIter,
+    DeriveActiveEnum,
+    Deserialize,
+    Serialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "reaction")]
+#[serde(rename_all = "lowercase")]
+pub enum Reaction {
+    #[sea_orm(string_value = "like")]
+    Like,
+    #[sea_orm(string_value = "love")]
+    Love,
+    #[sea_orm(string_value = "laugh")]
+    Laugh,
+    #[sea_orm(string_value = "wow")]
+    Wow,
+    #[sea_orm(string_value = "sad")]
+    Sad,
+    #[sea_orm(string_value = "angry")]
+    Angry,
+}
+
+/// Where a post is in its publication
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Deserialize, Serialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "post_status")]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    /// being written, only the author sees it
+    #[sea_orm(string_value = "draft")]
+    Draft,
+    /// published by the scheduler at `published_at`
+    #[sea_orm(string_value = "scheduled")]
+    Scheduled,
+    #[default]
+    #[sea_orm(string_value = "published")]
+    Published,
+    /// taken down by the author, kept for them
+    #[sea_orm(string_value = "archived")]
+    Archived,
+}
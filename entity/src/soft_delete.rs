@@ -0,0 +1,43 @@
+//! Soft delete for entities with a nullable `deleted_at` column
+//!
+//! Deleting only sets `deleted_at`, the row is removed for good by the purge job once the
+//! retention window has passed. Plain `find` is not scoped, so queries of the application
+//! state their scope: `find_active` keeps soft-deleted rows hidden, `find_deleted` and
+//! `find_with_deleted` are for the few places that need those rows.
+
+use sea_orm::{ColumnTrait, EntityTrait, PrimaryKeyTrait, QueryFilter, Select};
+
+pub trait SoftDelete: EntityTrait {
+    const DELETED_AT: Self::Column;
+
+    /// Rows that are not soft deleted
+    fn find_active() -> Select<Self> {
+        Self::find().filter(Self::DELETED_AT.is_null())
+    }
+
+    /// Row by primary key, unless it is soft deleted
+    fn find_active_by_id<T>(values: T) -> Select<Self>
+    where
+        T: Into<<Self::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+    {
+        Self::find_by_id(values).filter(Self::DELETED_AT.is_null())
+    }
+
+    /// Soft deleted rows only
+    fn find_deleted() -> Select<Self> {
+        Self::find().filter(Self::DELETED_AT.is_not_null())
+    }
+
+    /// Every row, soft deleted or not
+    fn find_with_deleted() -> Select<Self> {
+        Self::find()
+    }
+
+    /// Row by primary key, even if it is soft deleted
+    fn find_with_deleted_by_id<T>(values: T) -> Select<Self>
+    where
+        T: Into<<Self::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+    {
+        Self::find_by_id(values)
+    }
+}
@@ -16,11 +16,41 @@ pub struct Model {
     #[sea_orm(unique)]
     pub email: String,
     #[serde(with = "super::serde_time")]
+    pub email_verified_at: Option<DateTimeUtc>,
+    /// base32 TOTP secret, set on enrollment
+    #[serde(skip_serializing)]
+    pub totp_secret: Option<String>,
+    /// set once the enrollment was confirmed with a valid code
+    #[serde(with = "super::serde_time")]
+    pub totp_enabled_at: Option<DateTimeUtc>,
+    /// disabled users cannot log in
+    #[serde(with = "super::serde_time")]
+    pub disabled_at: Option<DateTimeUtc>,
+    /// soft deleted users are hidden and purged after the retention window
+    #[serde(with = "super::serde_time")]
+    pub deleted_at: Option<DateTimeUtc>,
+    #[serde(with = "super::serde_time")]
     pub created_at: Option<DateTimeUtc>,
     #[serde(with = "super::serde_time")]
     pub updated_at: Option<DateTimeUtc>,
     #[sea_orm(has_many)]
     pub posts: HasMany<super::post::Entity>,
+    #[sea_orm(has_many)]
+    pub personal_access_tokens: HasMany<super::personal_access_token::Entity>,
+    #[sea_orm(has_many)]
+    pub user_roles: HasMany<super::user_role::Entity>,
+    #[sea_orm(has_many)]
+    pub mfa_recovery_codes: HasMany<super::mfa_recovery_code::Entity>,
+    #[sea_orm(has_many)]
+    pub user_identities: HasMany<super::user_identity::Entity>,
+    #[sea_orm(has_many)]
+    pub comments: HasMany<super::comment::Entity>,
+    #[sea_orm(has_many)]
+    pub post_reactions: HasMany<super::post_reaction::Entity>,
+}
+
+impl super::soft_delete::SoftDelete for Entity {
+    const DELETED_AT: Column = Column::DeletedAt;
 }
 
 #[async_trait::async_trait]
@@ -35,9 +65,14 @@ impl ActiveModelBehavior for ActiveModel {
 
         if insert {
             self.created_at = sea_orm::Set(Some(now));
+        }
+
+        // the password is only `Set` when it was changed, loaded models keep it `Unchanged`
+        if self.password.is_set() {
             if let Some(password) = self.password.take() {
-                let password_hash = bcrypt::hash(password, 10)
-                    .map_err(|e| DbErr::Custom(format!("[before_save] bcrypt error: {:?}", e)))?;
+                let password_hash = super::password::hash(&password).map_err(|e| {
+                    DbErr::Custom(format!("[before_save] password hash error: {:?}", e))
+                })?;
 
                 self.password = sea_orm::Set(password_hash);
             }
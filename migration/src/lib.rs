@@ -1,12 +1,42 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20261017_000001_create_personal_access_token_table;
+mod m20261017_000002_create_rbac_tables;
+mod m20261017_000003_add_user_email_verified_at;
+mod m20261017_000004_create_mfa_tables;
+mod m20261017_000005_create_audit_log_table;
+mod m20261017_000006_add_soft_delete_columns;
+mod m20261017_000007_create_user_identity_table;
+mod m20261017_000008_add_post_visibility;
+mod m20261017_000009_add_post_search_vector;
+mod m20261017_000010_create_tag_tables;
+mod m20261017_000011_create_comment_table;
+mod m20261017_000012_create_post_reaction_table;
+mod m20261017_000013_add_post_status;
+mod m20261017_000014_create_user_follow_table;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20261017_000001_create_personal_access_token_table::Migration),
+            Box::new(m20261017_000002_create_rbac_tables::Migration),
+            Box::new(m20261017_000003_add_user_email_verified_at::Migration),
+            Box::new(m20261017_000004_create_mfa_tables::Migration),
+            Box::new(m20261017_000005_create_audit_log_table::Migration),
+            Box::new(m20261017_000006_add_soft_delete_columns::Migration),
+            Box::new(m20261017_000007_create_user_identity_table::Migration),
+            Box::new(m20261017_000008_add_post_visibility::Migration),
+            Box::new(m20261017_000009_add_post_search_vector::Migration),
+            Box::new(m20261017_000010_create_tag_tables::Migration),
+            Box::new(m20261017_000011_create_comment_table::Migration),
+            Box::new(m20261017_000012_create_post_reaction_table::Migration),
+            Box::new(m20261017_000013_add_post_status::Migration),
+            Box::new(m20261017_000014_create_user_follow_table::Migration),
+        ]
     }
 }
@@ -0,0 +1,63 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table("personal_access_token")
+                    .if_not_exists()
+                    .col(pk_auto("id"))
+                    .col(string("name"))
+                    .col(string_uniq("token_hash"))
+                    .col(string("scopes"))
+                    .col(date_time_null("expires_at"))
+                    .col(date_time_null("last_used_at"))
+                    .col(date_time("created_at"))
+                    .col(date_time("updated_at"))
+                    .col(integer("user_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-personal_access_token-user-id")
+                            .from("personal_access_token", "user_id")
+                            .to("user", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-personal_access_token-user-id")
+                    .table("personal_access_token")
+                    .col("user_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-personal_access_token-user-id")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("personal_access_token").to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
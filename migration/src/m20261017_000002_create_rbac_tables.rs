@@ -0,0 +1,142 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table("role")
+                    .if_not_exists()
+                    .col(pk_auto("id"))
+                    .col(string_uniq("name"))
+                    .col(string_null("description"))
+                    .col(date_time("created_at"))
+                    .col(date_time("updated_at"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table("permission")
+                    .if_not_exists()
+                    .col(pk_auto("id"))
+                    .col(string_uniq("name"))
+                    .col(string_null("description"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table("role_permission")
+                    .if_not_exists()
+                    .col(integer("role_id"))
+                    .col(integer("permission_id"))
+                    .primary_key(Index::create().col("role_id").col("permission_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-role_permission-role-id")
+                            .from("role_permission", "role_id")
+                            .to("role", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-role_permission-permission-id")
+                            .from("role_permission", "permission_id")
+                            .to("permission", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table("user_role")
+                    .if_not_exists()
+                    .col(integer("user_id"))
+                    .col(integer("role_id"))
+                    .primary_key(Index::create().col("user_id").col("role_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_role-user-id")
+                            .from("user_role", "user_id")
+                            .to("user", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_role-role-id")
+                            .from("user_role", "role_id")
+                            .to("role", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // seed the built-in roles, the admin role is granted every permission and the existing
+        // users the user role
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+            INSERT INTO "role" ("name", "description", "created_at", "updated_at") VALUES
+                ('admin', 'Manage every resource', now(), now()),
+                ('user', 'Default role of registered users', now(), now())
+            ON CONFLICT ("name") DO NOTHING;
+
+            INSERT INTO "permission" ("name", "description") VALUES
+                ('users:read', 'Read any user'),
+                ('users:write', 'Update any user'),
+                ('users:delete', 'Delete any user'),
+                ('audit_logs:read', 'Read the audit trail')
+            ON CONFLICT ("name") DO NOTHING;
+
+            INSERT INTO "role_permission" ("role_id", "permission_id")
+                SELECT r."id", p."id" FROM "role" r CROSS JOIN "permission" p WHERE r."name" = 'admin'
+            ON CONFLICT DO NOTHING;
+
+            -- users registered before roles existed get the default role
+            INSERT INTO "user_role" ("user_id", "role_id")
+                SELECT u."id", r."id" FROM "user" u CROSS JOIN "role" r WHERE r."name" = 'user'
+            ON CONFLICT DO NOTHING;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table("user_role").to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("role_permission").to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("permission").to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("role").to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
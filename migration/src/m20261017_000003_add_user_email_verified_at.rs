@@ -0,0 +1,39 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("user")
+                    .add_column(date_time_null("email_verified_at"))
+                    .to_owned(),
+            )
+            .await?;
+
+        // accounts made before verification existed are trusted, or requiring a verified
+        // email would lock every one of them out
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"UPDATE "user" SET "email_verified_at" = COALESCE("created_at", now())"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("user")
+                    .drop_column("email_verified_at")
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,80 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("user")
+                    .add_column(string_null("totp_secret"))
+                    .add_column(date_time_null("totp_enabled_at"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table("mfa_recovery_code")
+                    .if_not_exists()
+                    .col(pk_auto("id"))
+                    .col(string("code_hash"))
+                    .col(date_time_null("used_at"))
+                    .col(date_time("created_at"))
+                    .col(date_time("updated_at"))
+                    .col(integer("user_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-mfa_recovery_code-user-id")
+                            .from("mfa_recovery_code", "user_id")
+                            .to("user", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-mfa_recovery_code-user-id")
+                    .table("mfa_recovery_code")
+                    .col("user_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-mfa_recovery_code-user-id")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("mfa_recovery_code").to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("user")
+                    .drop_column("totp_secret")
+                    .drop_column("totp_enabled_at")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
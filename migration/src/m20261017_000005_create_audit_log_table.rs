@@ -0,0 +1,72 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("user")
+                    .add_column(date_time_null("disabled_at"))
+                    .to_owned(),
+            )
+            .await?;
+
+        // entries outlive the users they mention, the ids are kept as plain values
+        manager
+            .create_table(
+                Table::create()
+                    .table("audit_log")
+                    .if_not_exists()
+                    .col(pk_auto("id"))
+                    .col(integer_null("actor_id"))
+                    .col(string("action"))
+                    .col(integer_null("target_user_id"))
+                    .col(json_binary_null("details"))
+                    .col(string_null("ip"))
+                    .col(date_time("created_at"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-audit_log-target_user_id")
+                    .table("audit_log")
+                    .col("target_user_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-audit_log-target_user_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("audit_log").to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("user")
+                    .drop_column("disabled_at")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table("user_identity")
+                    .if_not_exists()
+                    .col(pk_auto("id"))
+                    .col(string("provider"))
+                    .col(string("subject"))
+                    .col(string_null("email"))
+                    .col(date_time("created_at"))
+                    .col(date_time("updated_at"))
+                    .col(integer("user_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_identity-user-id")
+                            .from("user_identity", "user_id")
+                            .to("user", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // an account of a provider is linked to a single user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-user_identity-provider-subject")
+                    .table("user_identity")
+                    .col("provider")
+                    .col("subject")
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-user_identity-user-id")
+                    .table("user_identity")
+                    .col("user_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx-user_identity-user-id").to_owned())
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-user_identity-provider-subject")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("user_identity").to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
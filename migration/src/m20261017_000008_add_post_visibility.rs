@@ -0,0 +1,73 @@
+use sea_orm_migration::{prelude::extension::postgres::Type, prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const VISIBILITIES: [&str; 3] = ["private", "unlisted", "public"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum("visibility")
+                    .values(VISIBILITIES)
+                    .to_owned(),
+            )
+            .await?;
+
+        // existing posts stay private
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("post")
+                    .add_column(
+                        enumeration("visibility", "visibility", VISIBILITIES)
+                            .default(Expr::cust("'private'::visibility")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // the public feed lists the newest public posts
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-post-visibility-created-at")
+                    .table("post")
+                    .col("visibility")
+                    .col("created_at")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-post-visibility-created-at")
+                    .table("post")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("post")
+                    .drop_column("visibility")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name("visibility").to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
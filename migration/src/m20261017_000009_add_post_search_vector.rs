@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Kept up to date by postgres, matches in the title rank above matches in the text.
+/// Queries must use the same `english` configuration to hit the index.
+const SEARCH_VECTOR: &str = "GENERATED ALWAYS AS (\
+    setweight(to_tsvector('english', coalesce(title, '')), 'A') || \
+    setweight(to_tsvector('english', coalesce(text, '')), 'B')\
+    ) STORED";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("post")
+                    .add_column(
+                        ColumnDef::new("search_vector")
+                            .custom("tsvector")
+                            .extra(SEARCH_VECTOR),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // the index builder has no GIN support
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE INDEX "idx-post-search-vector" ON "post" USING GIN ("search_vector")"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-post-search-vector")
+                    .table("post")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("post")
+                    .drop_column("search_vector")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
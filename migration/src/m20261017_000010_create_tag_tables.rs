@@ -0,0 +1,83 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table("tag")
+                    .if_not_exists()
+                    .col(pk_auto("id"))
+                    .col(string_uniq("name"))
+                    .col(date_time("created_at"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table("post_tag")
+                    .if_not_exists()
+                    .col(integer("post_id"))
+                    .col(integer("tag_id"))
+                    .primary_key(Index::create().col("post_id").col("tag_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-post_tag-post-id")
+                            .from("post_tag", "post_id")
+                            .to("post", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-post_tag-tag-id")
+                            .from("post_tag", "tag_id")
+                            .to("tag", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // the primary key covers lookups by post, filtering by tag needs its own index
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-post_tag-tag-id")
+                    .table("post_tag")
+                    .col("tag_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-post_tag-tag-id")
+                    .table("post_tag")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("post_tag").to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("tag").to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table("comment")
+                    .if_not_exists()
+                    .col(pk_auto("id"))
+                    .col(text("text"))
+                    .col(date_time("created_at"))
+                    .col(date_time("updated_at"))
+                    .col(integer("post_id"))
+                    .col(integer("user_id"))
+                    .col(integer_null("parent_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-comment-post-id")
+                            .from("comment", "post_id")
+                            .to("post", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-comment-user-id")
+                            .from("comment", "user_id")
+                            .to("user", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    // deleting a comment takes its replies with it
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-comment-parent-id")
+                            .from("comment", "parent_id")
+                            .to("comment", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-comment-post-id-parent-id")
+                    .table("comment")
+                    .col("post_id")
+                    .col("parent_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-comment-user-id")
+                    .table("comment")
+                    .col("user_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-comment-user-id")
+                    .table("comment")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-comment-post-id-parent-id")
+                    .table("comment")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("comment").to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
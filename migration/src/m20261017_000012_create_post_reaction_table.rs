@@ -0,0 +1,109 @@
+use sea_orm_migration::{prelude::extension::postgres::Type, prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const REACTIONS: [&str; 6] = ["like", "love", "laugh", "wow", "sad", "angry"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum("reaction")
+                    .values(REACTIONS)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table("post_reaction")
+                    .if_not_exists()
+                    .col(integer("post_id"))
+                    .col(integer("user_id"))
+                    .col(enumeration("reaction", "reaction", REACTIONS))
+                    .col(date_time("created_at"))
+                    .primary_key(
+                        Index::create()
+                            .col("post_id")
+                            .col("user_id")
+                            .col("reaction"),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-post_reaction-post-id")
+                            .from("post_reaction", "post_id")
+                            .to("post", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-post_reaction-user-id")
+                            .from("post_reaction", "user_id")
+                            .to("user", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // who reacted to a post, newest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-post_reaction-post-id-created-at")
+                    .table("post_reaction")
+                    .col("post_id")
+                    .col("created_at")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-post_reaction-user-id")
+                    .table("post_reaction")
+                    .col("user_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-post_reaction-user-id")
+                    .table("post_reaction")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-post_reaction-post-id-created-at")
+                    .table("post_reaction")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("post_reaction").to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name("reaction").to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use sea_orm_migration::{prelude::extension::postgres::Type, prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const STATUSES: [&str; 4] = ["draft", "scheduled", "published", "archived"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum("post_status")
+                    .values(STATUSES)
+                    .to_owned(),
+            )
+            .await?;
+
+        // existing posts were out as soon as they were created
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("post")
+                    .add_column(
+                        enumeration("status", "post_status", STATUSES)
+                            .default(Expr::cust("'published'::post_status")),
+                    )
+                    .add_column(date_time_null("published_at"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE post SET published_at = created_at")
+            .await?;
+
+        // the scheduler looks up the scheduled posts that are due, the feed the newest published
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-post-status-published-at")
+                    .table("post")
+                    .col("status")
+                    .col("published_at")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-post-status-published-at")
+                    .table("post")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("post")
+                    .drop_column("published_at")
+                    .drop_column("status")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name("post_status").to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
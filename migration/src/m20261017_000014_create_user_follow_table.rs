@@ -0,0 +1,68 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table("user_follow")
+                    .if_not_exists()
+                    .col(integer("follower_id"))
+                    .col(integer("followed_id"))
+                    .col(date_time("created_at"))
+                    .primary_key(Index::create().col("follower_id").col("followed_id"))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_follow-follower-id")
+                            .from("user_follow", "follower_id")
+                            .to("user", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_follow-followed-id")
+                            .from("user_follow", "followed_id")
+                            .to("user", "id")
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // the primary key covers lookups by follower, notifying needs the followers of a user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-user_follow-followed-id")
+                    .table("user_follow")
+                    .col("followed_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-user_follow-followed-id")
+                    .table("user_follow")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table("user_follow").to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,12 +1,17 @@
-use crate::core::config;
+use crate::{core::config, services::api_token};
 use utoipa::{
-    openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
     Modify, OpenApi,
 };
 
 pub const POST_TAG: &str = "Post";
 pub const USER_TAG: &str = "User";
 pub const UPLOAD_TAG: &str = "Upload";
+pub const TOKEN_TAG: &str = "Token";
+pub const MFA_TAG: &str = "MFA";
+pub const ADMIN_TAG: &str = "Admin";
+pub const OIDC_TAG: &str = "OpenID Connect";
+pub const WELL_KNOWN_TAG: &str = "Well-known";
 
 #[derive(OpenApi)]
 #[openapi(
@@ -24,7 +29,12 @@ pub const UPLOAD_TAG: &str = "Upload";
   tags(
     (name = USER_TAG, description = "User API endpoints"),
     (name = POST_TAG, description = "Post API endpoints"),
-    (name = UPLOAD_TAG, description = "Upload API endpoints")
+    (name = UPLOAD_TAG, description = "Upload API endpoints"),
+    (name = TOKEN_TAG, description = "Personal access token API endpoints"),
+    (name = MFA_TAG, description = "Two-factor authentication API endpoints"),
+    (name = ADMIN_TAG, description = "Admin API endpoints"),
+    (name = OIDC_TAG, description = "OpenID Connect login API endpoints"),
+    (name = WELL_KNOWN_TAG, description = "Metadata for other services")
   )
 )]
 pub struct ApiDoc;
@@ -40,9 +50,18 @@ impl Modify for SecurityAddon {
                     "cookie_security",
                     SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new(config.app_auth_key()))),
                 ),
+                (
+                    "bearer_security",
+                    SecurityScheme::Http(
+                        HttpBuilder::new()
+                            .scheme(HttpAuthScheme::Bearer)
+                            .bearer_format("JWT")
+                            .build(),
+                    ),
+                ),
                 (
                     "header_security",
-                    SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("PRIVATE-TOKEN"))),
+                    SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(api_token::HEADER))),
                 ),
             ]);
         }
@@ -1,14 +1,14 @@
 use crate::{
     api_doc::ApiDoc,
     core::{config, logger, state},
-    events, routes,
+    events, extractors, routes, services,
 };
 use axum::http::{header, HeaderName, Method, Request};
 use bb8_redis::RedisConnectionManager;
 use migration::{Migrator, MigratorTrait};
 use sea_orm::Database;
 use socketioxide::{handler::ConnectHandler, SocketIo};
-use std::{sync::Arc, time::Duration};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 use tower::ServiceBuilder;
 use tower_cookies::CookieManagerLayer;
 use tower_http::{
@@ -31,6 +31,8 @@ pub async fn start() -> anyhow::Result<()> {
     let config = config::Config::global();
     // Exiting the context of `main` will drop the `_guard` and any remaining logs should get flushed
     let _guard = logger::logger_init(config);
+    services::password::init(config)?;
+    services::jwt_keys::init(config)?;
 
     // database
     let db = Database::connect(config.database_url()).await?;
@@ -53,7 +55,32 @@ pub async fn start() -> anyhow::Result<()> {
 
     info!("Successfully connected to the redis");
 
-    let app_state = Arc::new(state::AppState { db, redis_pool });
+    // socket
+    let clients = events::store::Clients::default();
+    let (layer, io) = SocketIo::builder()
+        .with_state(clients.clone())
+        .with_state(redis_pool.clone())
+        .build_layer();
+    io.ns(
+        events::NAMESPACE,
+        events::handlers::on_connection.with(events::handlers::authenticate_middleware),
+    );
+
+    // mail
+    let mailer = services::mailer::from_config(config)?;
+
+    let app_state = Arc::new(state::AppState {
+        db,
+        redis_pool,
+        io,
+        clients,
+        mailer,
+    });
+
+    // background jobs
+    services::purge::spawn(app_state.db.clone());
+    services::publisher::spawn(app_state.clone());
+    services::jwt_keys::spawn();
 
     let x_request_id = HeaderName::from_static(REQUEST_ID_HEADER);
     let middleware = ServiceBuilder::new()
@@ -91,12 +118,15 @@ pub async fn start() -> anyhow::Result<()> {
                     header::AUTHORIZATION,
                     header::CONTENT_LANGUAGE,
                     header::CONTENT_TYPE,
+                    HeaderName::from_static(extractors::DEVICE_ID_HEADER),
+                    HeaderName::from_static(services::api_token::HEADER),
                     x_request_id,
                 ])
                 .allow_methods([
                     Method::GET,
                     Method::POST,
                     Method::PUT,
+                    Method::PATCH,
                     Method::DELETE,
                     Method::HEAD,
                     Method::OPTIONS,
@@ -108,18 +138,10 @@ pub async fn start() -> anyhow::Result<()> {
         );
     // build our application with a single route
     let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .nest("/api", routes::router())
+        .nest("/api", routes::router(app_state.clone()))
+        .merge(routes::well_known::route())
         .split_for_parts();
 
-    // socket
-    let (layer, io) = SocketIo::builder()
-        .with_state(events::store::Clients::default())
-        .build_layer();
-    io.ns(
-        "/socket",
-        events::handlers::on_connection.with(events::handlers::authenticate_middleware),
-    );
-
     let app = router
         .layer(middleware)
         // swagger ui
@@ -135,9 +157,12 @@ pub async fn start() -> anyhow::Result<()> {
 
     info!("Listening on {}", &addr);
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
 
     info!("Server shutdown");
 
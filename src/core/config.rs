@@ -1,12 +1,19 @@
 use crate::singleton;
-use jsonwebtoken::{DecodingKey, EncodingKey};
-use std::env;
+use jsonwebtoken::Algorithm;
+use std::{env, net::IpAddr, str::FromStr};
+
+/// Used when `JWT_SECRET` is not set, refused in production
+pub const DEFAULT_JWT_SECRET: &str = "default_jwt_secret";
 
 #[derive(Debug)]
 pub struct Config {
+    // app
+    app_env: AppEnv,
+
     // server
     server_host: String,
     server_port: u16,
+    trusted_proxies: Vec<IpAddr>,
 
     // database
     database_url: String,
@@ -17,7 +24,47 @@ pub struct Config {
     // auth
     app_auth_key: String,
     jwt_secret: String,
-    jwt_keys: Keys,
+    jwt_algorithm: Algorithm,
+    jwt_keys_dir: String,
+    jwt_keys_reload_interval: u64,
+    jwt_key_publish_delay: u64,
+    jwt_issuer: String,
+    jwt_audience: String,
+    jwt_leeway: u64,
+    access_token_ttl: i64,
+    refresh_token_ttl: i64,
+    auth_methods: Vec<AuthMethod>,
+    password_reset_ttl: i64,
+    email_verification_ttl: i64,
+    require_verified_email: bool,
+    mfa_issuer: String,
+    mfa_pending_ttl: i64,
+    login_max_attempts: u32,
+    login_ip_max_attempts: u32,
+    login_attempt_window: i64,
+    login_lockout: i64,
+    login_lockout_max: i64,
+    argon2_memory_kib: u32,
+    argon2_iterations: u32,
+    argon2_parallelism: u32,
+    api_url: String,
+    oidc_providers: Vec<OidcProvider>,
+
+    // mail
+    app_url: String,
+    mailer: MailerKind,
+    mail_from: String,
+    mail_outbox_dir: String,
+    smtp_host: String,
+    smtp_port: u16,
+    smtp_username: Option<String>,
+    smtp_password: Option<String>,
+
+    // data
+    soft_delete_retention: i64,
+    purge_interval: u64,
+    publish_interval: u64,
+    export_ttl: i64,
 
     // log
     log_dir: String,
@@ -44,39 +91,204 @@ impl Config {
         parse(raw).unwrap_or_else(|| panic!("❌ Invalid format for environment variable: {}", key))
     }
 
+    /// `default` when the variable is unset, an invalid value is refused like a required one
+    fn get_parsed_or<T: FromStr>(key: &str, default: T) -> T {
+        match env::var(key) {
+            Ok(_) => Self::must_get_parsed(key, |raw| raw.parse::<T>().ok()),
+            Err(_) => default,
+        }
+    }
+
     fn from_env() -> Self {
+        let app_env = env::var("APP_ENV")
+            .map(|v| {
+                AppEnv::parse(&v).unwrap_or_else(|| {
+                    panic!("❌ Invalid format for environment variable: APP_ENV")
+                })
+            })
+            .unwrap_or(AppEnv::Development);
+
         let server_host = env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-        let server_port = env::var("SERVER_PORT")
-            .ok()
-            .and_then(|v| v.parse::<u16>().ok())
-            .unwrap_or(3000);
+        let server_port = Self::get_parsed_or("SERVER_PORT", 3000);
+        // reverse proxies whose X-Forwarded-For and X-Real-IP headers are believed
+        let trusted_proxies = env::var("TRUSTED_PROXIES")
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|ip| !ip.is_empty())
+                    .map(|ip| {
+                        ip.parse::<IpAddr>().unwrap_or_else(|_| {
+                            panic!("❌ Invalid format for environment variable: TRUSTED_PROXIES")
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
 
         let database_url = Self::must_get("DATABASE_URL");
         let redis_url = Self::must_get("REDIS_URL");
 
         let app_auth_key =
             env::var("APP_AUTH_KEY").unwrap_or_else(|_| "default_auth_key".to_string());
-        let jwt_secret =
-            env::var("JWT_SECRET").unwrap_or_else(|_| "default_jwt_secret".to_string());
-        let jwt_keys = Keys::new(jwt_secret.as_bytes());
+        let jwt_secret = env::var("JWT_SECRET").unwrap_or_else(|_| DEFAULT_JWT_SECRET.to_string());
+        let jwt_algorithm = env::var("JWT_ALGORITHM")
+            .map(|v| {
+                Algorithm::from_str(&v)
+                    .ok()
+                    .filter(|algorithm| {
+                        matches!(
+                            algorithm,
+                            Algorithm::HS256 | Algorithm::RS256 | Algorithm::EdDSA
+                        )
+                    })
+                    .unwrap_or_else(|| {
+                        panic!("❌ Invalid format for environment variable: JWT_ALGORITHM")
+                    })
+            })
+            .unwrap_or(Algorithm::HS256);
+        let jwt_keys_dir = env::var("JWT_KEYS_DIR").unwrap_or_else(|_| "./keys".to_string());
+        // 5 minutes
+        let jwt_keys_reload_interval = Self::get_parsed_or("JWT_KEYS_RELOAD_INTERVAL", 60 * 5);
+        // 1 hour
+        let jwt_key_publish_delay = Self::get_parsed_or("JWT_KEY_PUBLISH_DELAY", 60 * 60);
+        // 15 minutes
+        let access_token_ttl = Self::get_parsed_or("ACCESS_TOKEN_TTL", 60 * 15);
+        // 30 days
+        let refresh_token_ttl = Self::get_parsed_or("REFRESH_TOKEN_TTL", 60 * 60 * 24 * 30);
+        // the order in which the auth guard looks for credentials
+        let auth_methods = env::var("AUTH_METHODS")
+            .map(|v| {
+                v.split(',')
+                    .map(|method| {
+                        AuthMethod::parse(method.trim()).unwrap_or_else(|| {
+                            panic!("❌ Invalid format for environment variable: AUTH_METHODS")
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_else(|_| vec![AuthMethod::Cookie, AuthMethod::Bearer, AuthMethod::ApiToken]);
+        // 30 minutes
+        let password_reset_ttl = Self::get_parsed_or("PASSWORD_RESET_TTL", 60 * 30);
+        // 1 day
+        let email_verification_ttl = Self::get_parsed_or("EMAIL_VERIFICATION_TTL", 60 * 60 * 24);
+        let require_verified_email = Self::get_parsed_or("REQUIRE_VERIFIED_EMAIL", false);
+        let mfa_issuer = env::var("MFA_ISSUER").unwrap_or_else(|_| "axum-web".to_string());
+        // 5 minutes
+        let mfa_pending_ttl = Self::get_parsed_or("MFA_PENDING_TTL", 60 * 5);
+        // failed logins allowed per email and per client ip within the window
+        let login_max_attempts = Self::get_parsed_or("LOGIN_MAX_ATTEMPTS", 5);
+        let login_ip_max_attempts = Self::get_parsed_or("LOGIN_IP_MAX_ATTEMPTS", 20);
+        // 15 minutes
+        let login_attempt_window = Self::get_parsed_or("LOGIN_ATTEMPT_WINDOW", 60 * 15);
+        // the first lockout lasts a minute, each following one twice as long, up to an hour
+        let login_lockout = Self::get_parsed_or("LOGIN_LOCKOUT", 60);
+        let login_lockout_max = Self::get_parsed_or("LOGIN_LOCKOUT_MAX", 60 * 60);
+        // OWASP recommended minimum for Argon2id
+        let argon2_memory_kib = Self::get_parsed_or("ARGON2_MEMORY_KIB", 19 * 1024);
+        let argon2_iterations = Self::get_parsed_or("ARGON2_ITERATIONS", 2);
+        let argon2_parallelism = Self::get_parsed_or("ARGON2_PARALLELISM", 1);
+        let api_url = env::var("API_URL")
+            .unwrap_or_else(|_| format!("http://{}:{}", server_host, server_port));
+        let jwt_issuer = env::var("JWT_ISSUER").unwrap_or_else(|_| api_url.clone());
+        let jwt_audience = env::var("JWT_AUDIENCE").unwrap_or_else(|_| api_url.clone());
+        // clock skew tolerated on the token timestamps, in seconds
+        let jwt_leeway = Self::get_parsed_or("JWT_LEEWAY", 30);
+        let oidc_providers = env::var("OIDC_PROVIDERS")
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(OidcProvider::from_env)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let app_url = env::var("APP_URL").unwrap_or_else(|_| "http://127.0.0.1:3000".to_string());
+        let mailer = env::var("MAILER")
+            .map(|v| {
+                MailerKind::parse(&v)
+                    .unwrap_or_else(|| panic!("❌ Invalid format for environment variable: MAILER"))
+            })
+            .unwrap_or(MailerKind::File);
+        let mail_from = env::var("MAIL_FROM").unwrap_or_else(|_| "no-reply@localhost".to_string());
+        let mail_outbox_dir =
+            env::var("MAIL_OUTBOX_DIR").unwrap_or_else(|_| "./outbox".to_string());
+        let smtp_host = match mailer {
+            MailerKind::Smtp => Self::must_get("SMTP_HOST"),
+            MailerKind::File => env::var("SMTP_HOST").unwrap_or_default(),
+        };
+        let smtp_port = Self::get_parsed_or("SMTP_PORT", 587);
+        let smtp_username = env::var("SMTP_USERNAME").ok();
+        let smtp_password = env::var("SMTP_PASSWORD").ok();
+
+        // 30 days
+        let soft_delete_retention = Self::get_parsed_or("SOFT_DELETE_RETENTION", 60 * 60 * 24 * 30);
+        // 1 hour
+        let purge_interval = Self::get_parsed_or("PURGE_INTERVAL", 60 * 60);
+        // 1 minute
+        let publish_interval = Self::get_parsed_or("PUBLISH_INTERVAL", 60);
+        // 1 day
+        let export_ttl = Self::get_parsed_or("EXPORT_TTL", 60 * 60 * 24);
 
         let log_dir = env::var("LOG_DIR").unwrap_or_else(|_| "./logs".to_string());
         let log_level = env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
 
         Self {
+            app_env,
             server_host,
             server_port,
+            trusted_proxies,
             database_url,
             redis_url,
             app_auth_key,
             jwt_secret,
-            jwt_keys,
+            jwt_algorithm,
+            jwt_keys_dir,
+            jwt_keys_reload_interval,
+            jwt_key_publish_delay,
+            jwt_issuer,
+            jwt_audience,
+            jwt_leeway,
+            access_token_ttl,
+            refresh_token_ttl,
+            auth_methods,
+            password_reset_ttl,
+            email_verification_ttl,
+            require_verified_email,
+            mfa_issuer,
+            mfa_pending_ttl,
+            login_max_attempts,
+            login_ip_max_attempts,
+            login_attempt_window,
+            login_lockout,
+            login_lockout_max,
+            argon2_memory_kib,
+            argon2_iterations,
+            argon2_parallelism,
+            api_url,
+            oidc_providers,
+            app_url,
+            mailer,
+            mail_from,
+            mail_outbox_dir,
+            smtp_host,
+            smtp_port,
+            smtp_username,
+            smtp_password,
+            soft_delete_retention,
+            purge_interval,
+            publish_interval,
+            export_ttl,
             log_dir,
             log_level,
         }
     }
 
     // getters
+    pub fn is_production(&self) -> bool {
+        self.app_env == AppEnv::Production
+    }
+
     pub fn server_host(&self) -> &str {
         &self.server_host
     }
@@ -85,6 +297,10 @@ impl Config {
         self.server_port
     }
 
+    pub fn trusted_proxies(&self) -> &[IpAddr] {
+        &self.trusted_proxies
+    }
+
     pub fn database_url(&self) -> &str {
         &self.database_url
     }
@@ -101,8 +317,176 @@ impl Config {
         &self.jwt_secret
     }
 
-    pub fn jwt_keys(&self) -> &Keys {
-        &self.jwt_keys
+    /// `iss` claim of the access tokens
+    pub fn jwt_issuer(&self) -> &str {
+        &self.jwt_issuer
+    }
+
+    /// `aud` claim of the access tokens, tokens for another audience are refused
+    pub fn jwt_audience(&self) -> &str {
+        &self.jwt_audience
+    }
+
+    /// Seconds of clock skew tolerated on `exp`
+    pub fn jwt_leeway(&self) -> u64 {
+        self.jwt_leeway
+    }
+
+    /// Algorithm signing the access tokens, `HS256` uses `JWT_SECRET`
+    pub fn jwt_algorithm(&self) -> Algorithm {
+        self.jwt_algorithm
+    }
+
+    /// Directory of the `{kid}.pem` private keys used with `RS256` and `EdDSA`
+    pub fn jwt_keys_dir(&self) -> &str {
+        &self.jwt_keys_dir
+    }
+
+    /// Seconds between two reloads of the key directory
+    pub fn jwt_keys_reload_interval(&self) -> u64 {
+        self.jwt_keys_reload_interval
+    }
+
+    /// Seconds a new key is published before it signs tokens
+    pub fn jwt_key_publish_delay(&self) -> u64 {
+        self.jwt_key_publish_delay
+    }
+
+    /// Name of the cookie carrying the opaque refresh token
+    pub fn app_refresh_key(&self) -> String {
+        format!("{}_refresh", self.app_auth_key)
+    }
+
+    /// Lifetime of an access token in seconds
+    pub fn access_token_ttl(&self) -> i64 {
+        self.access_token_ttl
+    }
+
+    /// Lifetime of a refresh token (and of its session) in seconds
+    pub fn refresh_token_ttl(&self) -> i64 {
+        self.refresh_token_ttl
+    }
+
+    pub fn auth_methods(&self) -> &[AuthMethod] {
+        &self.auth_methods
+    }
+
+    /// Lifetime of a password reset token in seconds
+    pub fn password_reset_ttl(&self) -> i64 {
+        self.password_reset_ttl
+    }
+
+    /// Lifetime of an email verification token in seconds
+    pub fn email_verification_ttl(&self) -> i64 {
+        self.email_verification_ttl
+    }
+
+    /// Whether protected routes reject users that have not verified their email
+    pub fn require_verified_email(&self) -> bool {
+        self.require_verified_email
+    }
+
+    /// Issuer shown by authenticator apps
+    pub fn mfa_issuer(&self) -> &str {
+        &self.mfa_issuer
+    }
+
+    /// Seconds a user has to enter the second factor after the password was accepted
+    pub fn mfa_pending_ttl(&self) -> i64 {
+        self.mfa_pending_ttl
+    }
+
+    pub fn login_max_attempts(&self) -> u32 {
+        self.login_max_attempts
+    }
+
+    pub fn login_ip_max_attempts(&self) -> u32 {
+        self.login_ip_max_attempts
+    }
+
+    /// Seconds over which failed logins are counted
+    pub fn login_attempt_window(&self) -> i64 {
+        self.login_attempt_window
+    }
+
+    /// Seconds of the first lockout, doubled on every following one
+    pub fn login_lockout(&self) -> i64 {
+        self.login_lockout
+    }
+
+    pub fn login_lockout_max(&self) -> i64 {
+        self.login_lockout_max
+    }
+
+    /// Argon2id cost parameters: memory in KiB, iterations, parallelism
+    pub fn argon2_params(&self) -> (u32, u32, u32) {
+        (
+            self.argon2_memory_kib,
+            self.argon2_iterations,
+            self.argon2_parallelism,
+        )
+    }
+
+    /// Public url of this api, used to build the OpenID Connect callback urls
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    pub fn oidc_provider(&self, name: &str) -> Option<&OidcProvider> {
+        self.oidc_providers
+            .iter()
+            .find(|provider| provider.name == name)
+    }
+
+    /// Public url of the frontend, used to build links sent by mail
+    pub fn app_url(&self) -> &str {
+        &self.app_url
+    }
+
+    pub fn mailer(&self) -> MailerKind {
+        self.mailer
+    }
+
+    pub fn mail_from(&self) -> &str {
+        &self.mail_from
+    }
+
+    pub fn mail_outbox_dir(&self) -> &str {
+        &self.mail_outbox_dir
+    }
+
+    pub fn smtp_host(&self) -> &str {
+        &self.smtp_host
+    }
+
+    pub fn smtp_port(&self) -> u16 {
+        self.smtp_port
+    }
+
+    pub fn smtp_credentials(&self) -> Option<(&str, &str)> {
+        self.smtp_username
+            .as_deref()
+            .zip(self.smtp_password.as_deref())
+    }
+
+    /// Seconds soft-deleted rows are kept before they are purged
+    pub fn soft_delete_retention(&self) -> i64 {
+        self.soft_delete_retention
+    }
+
+    /// Seconds between two runs of the purge job
+    pub fn purge_interval(&self) -> u64 {
+        self.purge_interval
+    }
+
+    /// Seconds between two checks for scheduled posts to publish
+    pub fn publish_interval(&self) -> u64 {
+        self.publish_interval
+    }
+
+    /// Seconds a personal data export can be downloaded for
+    pub fn export_ttl(&self) -> i64 {
+        self.export_ttl
     }
 
     pub fn log_dir(&self) -> &str {
@@ -114,25 +498,86 @@ impl Config {
     }
 }
 
-#[derive(Debug)]
-pub struct Keys {
-    encoding: EncodingKey,
-    decoding: DecodingKey,
+/// Environment the app runs in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AppEnv {
+    Development,
+    Production,
 }
 
-impl Keys {
-    fn new(secret: &[u8]) -> Self {
-        Self {
-            encoding: EncodingKey::from_secret(secret),
-            decoding: DecodingKey::from_secret(secret),
+impl AppEnv {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "development" => Some(Self::Development),
+            "production" => Some(Self::Production),
+            _ => None,
+        }
+    }
+}
+
+/// Where the auth guard may find credentials
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthMethod {
+    /// access token in the session cookie
+    Cookie,
+    /// access token in the `Authorization: Bearer` header
+    Bearer,
+    /// personal access token in the `PRIVATE-TOKEN` header
+    ApiToken,
+}
+
+impl AuthMethod {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "cookie" => Some(Self::Cookie),
+            "bearer" => Some(Self::Bearer),
+            "token" => Some(Self::ApiToken),
+            _ => None,
         }
     }
+}
+
+/// How outgoing mail is delivered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MailerKind {
+    /// through an SMTP relay
+    Smtp,
+    /// written to `MAIL_OUTBOX_DIR`, for tests and local development
+    File,
+}
 
-    pub fn encoding(&self) -> &EncodingKey {
-        &self.encoding
+impl MailerKind {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "smtp" => Some(Self::Smtp),
+            "file" => Some(Self::File),
+            _ => None,
+        }
     }
+}
+
+/// An OpenID Connect provider users can log in with, configured through
+/// `OIDC_{NAME}_ISSUER`, `OIDC_{NAME}_CLIENT_ID`, `OIDC_{NAME}_CLIENT_SECRET`
+/// and optionally `OIDC_{NAME}_SCOPES`
+#[derive(Clone, Debug)]
+pub struct OidcProvider {
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: String,
+}
 
-    pub fn decoding(&self) -> &DecodingKey {
-        &self.decoding
+impl OidcProvider {
+    fn from_env(name: &str) -> Self {
+        let prefix = format!("OIDC_{}", name.to_uppercase());
+        Self {
+            name: name.to_lowercase(),
+            issuer: Config::must_get(&format!("{prefix}_ISSUER")),
+            client_id: Config::must_get(&format!("{prefix}_CLIENT_ID")),
+            client_secret: Config::must_get(&format!("{prefix}_CLIENT_SECRET")),
+            scopes: env::var(format!("{prefix}_SCOPES"))
+                .unwrap_or_else(|_| "openid email profile".to_string()),
+        }
     }
 }
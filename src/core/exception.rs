@@ -1,10 +1,10 @@
 //! convert errors into responses
 
-use std::io;
+use std::{convert::Infallible, io};
 
 use axum::{
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
 };
 use sea_orm::SqlErr;
 use serde_json::json;
@@ -54,6 +54,9 @@ pub enum HttpException {
     /// 422
     #[error("Unprocessable Entity")]
     UnprocessableEntityException(Option<String>),
+    /// 429
+    #[error("Too Many Requests")]
+    TooManyRequestsException(Option<String>),
     /// 500
     #[error("Internal Server Error")]
     InternalServerErrorException(Option<String>),
@@ -122,6 +125,10 @@ impl HttpException {
                 StatusCode::UNPROCESSABLE_ENTITY,
                 "Unprocessable Entity".to_string(),
             ),
+            HttpException::TooManyRequestsException(_) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too Many Requests".to_string(),
+            ),
             HttpException::InternalServerErrorException(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal Server Error".to_string(),
@@ -181,6 +188,7 @@ impl IntoResponse for HttpException {
             | HttpException::UnsupportedMediaTypeException(Some(msg))
             | HttpException::ImATeapotException(Some(msg))
             | HttpException::UnprocessableEntityException(Some(msg))
+            | HttpException::TooManyRequestsException(Some(msg))
             | HttpException::InternalServerErrorException(Some(msg))
             | HttpException::NotImplementedException(Some(msg))
             | HttpException::BadGatewayException(Some(msg))
@@ -199,6 +207,20 @@ impl IntoResponse for HttpException {
     }
 }
 
+/// `Retry-After` header telling the client how many seconds to wait, sent along with an
+/// exception as `(RetryAfter(seconds), exception)`
+pub struct RetryAfter(pub u64);
+
+impl IntoResponseParts for RetryAfter {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        res.headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(self.0));
+        Ok(res)
+    }
+}
+
 impl From<io::Error> for HttpException {
     fn from(err: io::Error) -> Self {
         tracing::error!(%err);
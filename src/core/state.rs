@@ -1,8 +1,16 @@
+use crate::{events::store::Clients, services::mailer::Mailer};
 use bb8::Pool;
 use bb8_redis::RedisConnectionManager;
+use socketioxide::SocketIo;
+use std::sync::Arc;
+
+pub type RedisPool = Pool<RedisConnectionManager>;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: sea_orm::DatabaseConnection,
-    pub redis_pool: Pool<RedisConnectionManager>,
+    pub redis_pool: RedisPool,
+    pub io: SocketIo,
+    pub clients: Clients,
+    pub mailer: Arc<dyn Mailer>,
 }
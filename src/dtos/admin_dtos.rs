@@ -0,0 +1,50 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct ListUsersDto {
+    #[validate(range(min = 1, max = 10000, message = "Invalid page"))]
+    pub page: Option<u64>,
+    #[validate(range(min = 1, max = 100, message = "Invalid page size"))]
+    pub per_page: Option<u64>,
+    /// Matches part of the name or email, case insensitive
+    pub q: Option<String>,
+    /// Only users holding this role
+    pub role: Option<String>,
+    pub disabled: Option<bool>,
+    pub verified: Option<bool>,
+    /// List soft-deleted users instead of active ones
+    pub deleted: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct AdminUserParam {
+    #[validate(range(min = 1, message = "Invalid id"))]
+    pub id: i32,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct DisableUserDto {
+    /// Recorded in the audit trail
+    #[validate(length(max = 255, message = "Invalid reason"))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct ListAuditLogsDto {
+    #[validate(range(min = 1, max = 10000, message = "Invalid page"))]
+    pub page: Option<u64>,
+    #[validate(range(min = 1, max = 100, message = "Invalid page size"))]
+    pub per_page: Option<u64>,
+    pub actor_id: Option<i32>,
+    pub target_user_id: Option<i32>,
+    pub action: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct RevokeTokenDto {
+    /// Access token to refuse from now on
+    #[validate(length(min = 1, message = "Invalid token"))]
+    pub token: String,
+}
@@ -0,0 +1,40 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+pub const MAX_COMMENT_LENGTH: u64 = 4096;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct QueryCommentDto {
+    /// Post id
+    #[validate(range(min = 1, message = "Invalid id"))]
+    pub id: i32,
+    #[validate(range(min = 1, message = "Invalid comment id"))]
+    pub comment_id: i32,
+}
+
+/// Item create comment.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateCommentDto {
+    #[validate(length(min = 1, max = MAX_COMMENT_LENGTH, message = "Invalid text"))]
+    pub text: String,
+    /// Comment replied to, on the same post
+    #[validate(range(min = 1, message = "Invalid parent id"))]
+    pub parent_id: Option<i32>,
+}
+
+/// Item edit comment.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct UpdateCommentDto {
+    #[validate(length(min = 1, max = MAX_COMMENT_LENGTH, message = "Invalid text"))]
+    pub text: String,
+}
+
+/// Which level of a thread to list, paging is read by the `Pagination` extractor.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct ListCommentsDto {
+    /// Replies to this comment, the top of the threads when left out
+    #[validate(range(min = 1, message = "Invalid parent id"))]
+    pub parent_id: Option<i32>,
+}
@@ -1,2 +1,6 @@
+pub mod admin_dtos;
+pub mod comment_dtos;
+pub mod oidc_dtos;
 pub mod post_dtos;
+pub mod reaction_dtos;
 pub mod user_dtos;
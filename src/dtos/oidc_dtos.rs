@@ -0,0 +1,29 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct OidcProviderParam {
+    #[validate(length(min = 1, message = "Invalid provider"))]
+    pub provider: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct OidcLoginQuery {
+    /// Path of the frontend to return to after the login
+    pub redirect: Option<String>,
+}
+
+/// Sent by the provider, either `code` and `state` or an `error`
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct IdentityParam {
+    #[validate(range(min = 1, message = "Invalid id"))]
+    pub id: i32,
+}
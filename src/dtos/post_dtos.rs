@@ -1,8 +1,104 @@
-use serde::Deserialize;
-use validator::Validate;
+use crate::{extractors::SortOrder, services::tag};
+use chrono::{DateTime, Utc};
+use entity::sea_orm_active_enums::{Category, PostStatus, Visibility};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct QueryPostDto {
     #[validate(range(min = 1, message = "Invalid id"))]
     pub id: i32,
 }
+
+/// Item create or replace post, a replaced post gets every field of the body except the
+/// visibility, tags and status left out of it.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct PostDto {
+    #[validate(length(min = 1, message = "Invalid title"))]
+    pub title: String,
+    #[validate(length(min = 1, message = "Invalid text"))]
+    pub text: String,
+    #[schema(value_type = Option<String>, default = "Feed")]
+    pub category: Option<Category>,
+    /// `private` for a new post, kept when a post is replaced
+    #[schema(value_type = Option<String>, default = "private")]
+    pub visibility: Option<Visibility>,
+    /// none for a new post, kept when a post is replaced
+    #[validate(custom(function = "validate_tags"))]
+    pub tags: Option<Vec<String>>,
+    /// `published` for a new post, kept when a post is replaced
+    #[schema(value_type = Option<String>, default = "published")]
+    pub status: Option<PostStatus>,
+    /// When a scheduled post goes out, RFC 3339
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// Item partially update post, only the given fields are changed.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct PatchPostDto {
+    #[validate(length(min = 1, message = "Invalid title"))]
+    pub title: Option<String>,
+    #[validate(length(min = 1, message = "Invalid text"))]
+    pub text: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub category: Option<Category>,
+    #[schema(value_type = Option<String>)]
+    pub visibility: Option<Visibility>,
+    #[validate(custom(function = "validate_tags"))]
+    pub tags: Option<Vec<String>>,
+    #[schema(value_type = Option<String>)]
+    pub status: Option<PostStatus>,
+    /// When a scheduled post goes out, RFC 3339
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// Column a post listing is sorted by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PostSort {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    PublishedAt,
+}
+
+/// Filters and order of a post listing, paging is read by the `Pagination` extractor.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct ListPostsDto {
+    #[schema(value_type = Option<String>)]
+    pub category: Option<Category>,
+    #[schema(value_type = Option<String>)]
+    pub visibility: Option<Visibility>,
+    #[schema(value_type = Option<String>)]
+    pub status: Option<PostStatus>,
+    /// Created at or after, RFC 3339
+    pub from: Option<DateTime<Utc>>,
+    /// Created before, RFC 3339
+    pub to: Option<DateTime<Utc>>,
+    /// Part of the title, case insensitive
+    #[validate(length(max = 255, message = "Invalid title"))]
+    pub title: Option<String>,
+    /// Comma separated, only posts carrying every one of them
+    #[validate(length(max = 512, message = "Invalid tags"))]
+    pub tags: Option<String>,
+    pub sort: Option<PostSort>,
+    pub order: Option<SortOrder>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct SearchPostsDto {
+    /// Search terms, quotes, `or` and `-` work as in web search engines
+    #[validate(length(min = 1, max = 255, message = "Invalid search"))]
+    pub q: String,
+    /// Only search the posts of the current user
+    pub mine: Option<bool>,
+}
+
+fn validate_tags(tags: &[String]) -> Result<(), ValidationError> {
+    if tag::normalize(tags).len() > tag::MAX_TAGS || tags.iter().any(|name| !tag::is_valid(name)) {
+        return Err(ValidationError::new("tags").with_message("Invalid tags".into()));
+    }
+
+    Ok(())
+}
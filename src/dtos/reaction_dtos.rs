@@ -0,0 +1,19 @@
+use entity::sea_orm_active_enums::Reaction;
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct QueryReactionDto {
+    /// Post id
+    #[validate(range(min = 1, message = "Invalid id"))]
+    pub id: i32,
+    pub reaction: Reaction,
+}
+
+/// Which reactions to list, paging is read by the `Pagination` extractor.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct ListReactionsDto {
+    #[schema(value_type = Option<String>)]
+    pub reaction: Option<Reaction>,
+}
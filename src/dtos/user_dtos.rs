@@ -1,6 +1,7 @@
+use crate::services::api_token;
 use serde::Deserialize;
 use utoipa::ToSchema;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 /// Item create user.
 #[derive(Debug, Deserialize, Validate, ToSchema)]
@@ -21,6 +22,46 @@ pub(crate) struct LoginUserDto {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LoginMfaDto {
+    #[validate(length(min = 1, message = "Invalid mfa token"))]
+    pub mfa_token: String,
+    /// TOTP code or recovery code
+    #[validate(length(min = 6, max = 32, message = "Invalid code"))]
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct UpdateProfileDto {
+    #[validate(length(min = 1, message = "Invalid name"))]
+    pub name: Option<String>,
+    /// A new email has to be verified again
+    #[validate(email(message = "Invalid email"))]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ChangePasswordDto {
+    pub current_password: String,
+    #[validate(length(min = 8, message = "Invalid password"))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct ExportParam {
+    #[validate(length(min = 1, max = 64, message = "Invalid id"))]
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct EraseAccountDto {
+    pub password: String,
+    /// TOTP or recovery code, required when two-factor authentication is enabled
+    pub code: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub(crate) struct DeleteUserParam {
     #[validate(range(min = 1, message = "Invalid id"))]
@@ -28,11 +69,83 @@ pub(crate) struct DeleteUserParam {
 }
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
-pub(crate) struct DeleteUserDto {
-    pub thoroughly: Option<bool>,
+pub(crate) struct FollowUserParam {
+    #[validate(range(min = 1, message = "Invalid id"))]
+    pub id: i32,
 }
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub(crate) struct RedirectParam {
     pub uri: Option<String>,
 }
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RefreshTokenDto {
+    /// Falls back to the refresh token cookie when omitted
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct SessionParam {
+    #[validate(length(min = 1, message = "Invalid session id"))]
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateTokenDto {
+    #[validate(length(min = 1, max = 64, message = "Invalid name"))]
+    pub name: String,
+    /// Defaults to `["read"]`
+    #[validate(custom(function = "validate_scopes"))]
+    pub scopes: Option<Vec<String>>,
+    /// Days until the token expires, the token never expires when omitted
+    #[validate(range(min = 1, max = 365, message = "Invalid expiry"))]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct TokenParam {
+    #[validate(range(min = 1, message = "Invalid id"))]
+    pub id: i32,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct ForgotPasswordDto {
+    #[validate(email(message = "Invalid email"))]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct ResetPasswordDto {
+    #[validate(length(min = 1, message = "Invalid token"))]
+    pub token: String,
+    #[validate(length(min = 8, message = "Invalid password"))]
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct VerifyEmailDto {
+    #[validate(length(min = 1, message = "Invalid token"))]
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub(crate) struct MfaCodeDto {
+    /// TOTP code, or a recovery code where noted
+    #[validate(length(min = 6, max = 32, message = "Invalid code"))]
+    pub code: String,
+}
+
+fn validate_scopes(scopes: &[String]) -> Result<(), ValidationError> {
+    if scopes.is_empty()
+        || scopes
+            .iter()
+            .any(|scope| !api_token::SCOPES.contains(&scope.as_str()))
+    {
+        return Err(ValidationError::new("scopes").with_message("Invalid scopes".into()));
+    }
+
+    Ok(())
+}
@@ -1,5 +1,9 @@
-use crate::core::config;
-use crate::{guards::jwt_decode, utils::get_cookie_value};
+use crate::core::{config, state::RedisPool};
+use crate::{
+    guards::jwt_decode,
+    services::{session, token_denylist},
+    utils::get_cookie_value,
+};
 use anyhow::{anyhow, Result};
 use axum::http::header;
 use socketioxide::extract::{Extension, SocketRef, State};
@@ -20,7 +24,7 @@ pub async fn on_connection(
     socket.on_disconnect(
         async |_s: SocketRef, Extension::<Arc<Client>>(client), State::<Clients>(clients)| {
             // remove client from clients
-            clients.remove(client.user_id);
+            clients.remove(&client);
         },
     );
 }
@@ -30,6 +34,7 @@ pub async fn on_connection(
 pub async fn authenticate_middleware(
     socket: SocketRef,
     State(clients): State<Clients>,
+    State(redis_pool): State<RedisPool>,
 ) -> Result<()> {
     let cookies = socket
         .req_parts()
@@ -39,9 +44,19 @@ pub async fn authenticate_middleware(
         .ok_or(anyhow!("Unauthorized"))?;
     let config = config::Config::global();
     let cookie = get_cookie_value(cookies, config.app_auth_key()).ok_or(anyhow!("Unauthorized"))?;
-    let claims = jwt_decode(&cookie, config.jwt_keys().decoding()).expect("Unauthorized");
+    let claims = jwt_decode(&cookie)?;
+    // the session or the token itself may have been revoked while the token is still valid
+    let active = session::is_active(&redis_pool, &claims.sid)
+        .await
+        .map_err(|err| anyhow!(err))?;
+    let revoked = token_denylist::is_revoked(&redis_pool, &claims.jti)
+        .await
+        .map_err(|err| anyhow!(err))?;
+    if !active || revoked {
+        return Err(anyhow!("Unauthorized"));
+    }
 
-    let client = Arc::new(Client::new(socket.id, claims.user_id));
+    let client = Arc::new(Client::new(socket.id, claims.user_id, claims.sid));
     socket.extensions.insert(client.clone());
     clients.add(client);
     Ok(())
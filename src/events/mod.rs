@@ -1,2 +1,52 @@
+use serde::Serialize;
+use socketioxide::SocketIo;
+
+use self::store::Clients;
+
 pub mod handlers;
 pub mod store;
+
+pub const NAMESPACE: &str = "/socket";
+
+/// A personal data export can be downloaded
+pub const EXPORT_READY: &str = "export:ready";
+/// A personal data export could not be built
+pub const EXPORT_FAILED: &str = "export:failed";
+/// Someone commented on a post of the user
+pub const COMMENT_CREATED: &str = "comment:created";
+/// A user the user follows published a post
+pub const POST_PUBLISHED: &str = "post:published";
+
+/// Emit an event to every socket of a user
+pub fn emit_to_user<T>(io: &SocketIo, clients: &Clients, user_id: i32, event: &str, data: &T)
+where
+    T: Serialize + ?Sized,
+{
+    let Some(ns) = io.of(NAMESPACE) else {
+        return;
+    };
+
+    for client in clients.get(user_id) {
+        if let Some(socket) = ns.get_socket(client.socket_id) {
+            if let Err(err) = socket.emit(event, data) {
+                tracing::error!(%err);
+            }
+        }
+    }
+}
+
+/// Disconnect every socket a user opened with the given login session
+pub fn disconnect_session(io: &SocketIo, clients: &Clients, user_id: i32, session_id: &str) {
+    let Some(ns) = io.of(NAMESPACE) else {
+        return;
+    };
+
+    for client in clients.by_session(user_id, session_id) {
+        if let Some(socket) = ns.get_socket(client.socket_id) {
+            if let Err(err) = socket.disconnect() {
+                tracing::error!(%err);
+            }
+        }
+        clients.remove(&client);
+    }
+}
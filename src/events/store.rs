@@ -12,35 +12,55 @@ use socketioxide::socket::Sid;
 pub struct Client {
     pub socket_id: Sid,
     pub user_id: i32,
+    /// login session the socket was authenticated with
+    pub session_id: String,
 }
 
 impl Client {
-    pub fn new(socket_id: Sid, user_id: i32) -> Self {
-        Self { socket_id, user_id }
+    pub fn new(socket_id: Sid, user_id: i32, session_id: String) -> Self {
+        Self {
+            socket_id,
+            user_id,
+            session_id,
+        }
     }
 }
 
+/// Connected clients grouped by user, a user may be connected from several devices
 #[derive(Clone, Debug, Default)]
-pub struct Clients(Arc<RwLock<HashMap<i32, Arc<Client>>>>);
+pub struct Clients(Arc<RwLock<HashMap<i32, Vec<Arc<Client>>>>>);
 
 impl Clients {
-    pub fn get(&self, user_id: i32) -> Option<Arc<Client>> {
+    pub fn get(&self, user_id: i32) -> Vec<Arc<Client>> {
         if let Ok(clients) = self.0.read() {
-            clients.get(&user_id).cloned()
+            clients.get(&user_id).cloned().unwrap_or_default()
         } else {
-            None
+            Vec::new()
         }
     }
 
+    /// Clients connected with the given login session
+    pub fn by_session(&self, user_id: i32, session_id: &str) -> Vec<Arc<Client>> {
+        self.get(user_id)
+            .into_iter()
+            .filter(|client| client.session_id == session_id)
+            .collect()
+    }
+
     pub fn add(&self, client: Arc<Client>) {
         if let Ok(mut clients) = self.0.write() {
-            clients.insert(client.user_id, client);
+            clients.entry(client.user_id).or_default().push(client);
         }
     }
 
-    pub fn remove(&self, user_id: i32) {
+    pub fn remove(&self, client: &Client) {
         if let Ok(mut clients) = self.0.write() {
-            clients.remove(&user_id);
+            if let Some(sockets) = clients.get_mut(&client.user_id) {
+                sockets.retain(|c| c.socket_id != client.socket_id);
+                if sockets.is_empty() {
+                    clients.remove(&client.user_id);
+                }
+            }
         }
     }
 }
@@ -0,0 +1,102 @@
+// We define our own `ClientInfo` extractor that collects what we know about the caller
+
+use crate::core::config;
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts, HeaderMap},
+};
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+pub const DEVICE_ID_HEADER: &str = "x-device-id";
+
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub device_id: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let headers = &parts.headers;
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let ip = client_ip(headers, peer, config::Config::global().trusted_proxies());
+
+        Ok(Self {
+            ip,
+            user_agent: header_value(headers, header::USER_AGENT.as_str()),
+            device_id: header_value(headers, DEVICE_ID_HEADER),
+        })
+    }
+}
+
+/// Address of the caller. The headers of a reverse proxy are only believed when the request
+/// comes from one of the trusted proxies, anyone else could put any address in them.
+fn client_ip(headers: &HeaderMap, peer: Option<IpAddr>, trusted: &[IpAddr]) -> Option<String> {
+    let is_trusted = |ip: &IpAddr| trusted.contains(ip);
+    if !peer.as_ref().is_some_and(is_trusted) {
+        return peer.map(|ip| ip.to_string());
+    }
+
+    // every proxy appends the address it got the request from, the last one not added by a
+    // trusted proxy is the client
+    let forwarded = header_value(headers, "x-forwarded-for").and_then(|v| {
+        v.split(',')
+            .rev()
+            .map(|ip| ip.trim().parse::<IpAddr>())
+            .find(|ip| !ip.as_ref().is_ok_and(is_trusted))
+            .and_then(Result::ok)
+    });
+    let real_ip = || header_value(headers, "x-real-ip").and_then(|v| v.trim().parse().ok());
+
+    forwarded.or_else(real_ip).or(peer).map(|ip| ip.to_string())
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_ip() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            "198.51.100.1, 203.0.113.7, 10.0.0.1".parse().unwrap(),
+        );
+
+        // spoofed by a client talking to us directly
+        assert_eq!(
+            client_ip(&headers, Some(client), &[proxy]),
+            Some(client.to_string())
+        );
+        // the entry before the trusted proxies, not the one the client made up
+        assert_eq!(
+            client_ip(&headers, Some(proxy), &[proxy]),
+            Some(client.to_string())
+        );
+        assert_eq!(
+            client_ip(&HeaderMap::new(), Some(proxy), &[proxy]),
+            Some(proxy.to_string())
+        );
+    }
+}
@@ -0,0 +1,147 @@
+// We define our own `Pagination` extractor that reads the paging parameters of list endpoints
+
+use crate::core::exception::HttpException;
+use axum::{
+    extract::{FromRequestParts, Query as AxumQuery},
+    http::request::Parts,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+pub const DEFAULT_PER_PAGE: u64 = 20;
+
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_mode"))]
+struct PaginationParams {
+    // deeper pages are read by cursor, this also keeps the offset from overflowing
+    #[validate(range(min = 1, max = 10000, message = "Invalid page"))]
+    page: Option<u64>,
+    #[validate(range(min = 1, max = 100, message = "Invalid page size"))]
+    per_page: Option<u64>,
+    cursor: Option<String>,
+}
+
+fn validate_mode(params: &PaginationParams) -> Result<(), ValidationError> {
+    if params.page.is_some() && params.cursor.is_some() {
+        return Err(ValidationError::new("pagination")
+            .with_message("Either page or cursor can be given".into()));
+    }
+
+    Ok(())
+}
+
+/// How a list is paged, by page number or by cursor when a `cursor` parameter is given.
+/// An empty `cursor` asks for the first page in cursor mode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pagination {
+    Offset {
+        page: u64,
+        per_page: u64,
+    },
+    Cursor {
+        /// `None` on the first page
+        cursor: Option<String>,
+        per_page: u64,
+    },
+}
+
+impl Pagination {
+    pub fn per_page(&self) -> u64 {
+        match self {
+            Self::Offset { per_page, .. } | Self::Cursor { per_page, .. } => *per_page,
+        }
+    }
+
+    /// Position after which the page starts, decoded from the cursor
+    pub fn position<T: DeserializeOwned>(&self) -> Result<Option<T>, HttpException> {
+        let Self::Cursor {
+            cursor: Some(cursor),
+            ..
+        } = self
+        else {
+            return Ok(None);
+        };
+
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .map(Some)
+            .ok_or(HttpException::BadRequestException(Some(
+                "Invalid cursor".to_string(),
+            )))
+    }
+}
+
+/// Opaque cursor of the page following `position`
+pub fn encode_cursor<T: Serialize>(position: &T) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(position).unwrap_or_default())
+}
+
+impl<S> FromRequestParts<S> for Pagination
+where
+    S: Send + Sync,
+{
+    type Rejection = super::ParserError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let AxumQuery(params) =
+            AxumQuery::<PaginationParams>::from_request_parts(parts, state).await?;
+        params.validate()?;
+
+        let per_page = params.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        Ok(match params.cursor {
+            Some(cursor) => Self::Cursor {
+                cursor: Some(cursor).filter(|cursor| !cursor.is_empty()),
+                per_page,
+            },
+            None => Self::Offset {
+                page: params.page.unwrap_or(1),
+                per_page,
+            },
+        })
+    }
+}
+
+/// Direction of a sorted list
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl From<SortOrder> for sea_orm::Order {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => Self::Asc,
+            SortOrder::Desc => Self::Desc,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = encode_cursor(&(1_700_000_000_000_000_i64, 42));
+        let pagination = Pagination::Cursor {
+            cursor: Some(cursor),
+            per_page: DEFAULT_PER_PAGE,
+        };
+        assert_eq!(
+            pagination.position::<(i64, i32)>().unwrap(),
+            Some((1_700_000_000_000_000, 42))
+        );
+
+        let tampered = Pagination::Cursor {
+            cursor: Some("not a cursor".to_string()),
+            per_page: DEFAULT_PER_PAGE,
+        };
+        assert!(tampered.position::<(i64, i32)>().is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts},
+};
+use std::sync::Arc;
+use tower_cookies::Cookies;
+
+use crate::{
+    core::{
+        config::{self, AuthMethod},
+        exception::HttpException,
+        state::AppState,
+    },
+    services::api_token,
+};
+
+/// Authenticates the request with the first credentials found, in the order of
+/// `Config::auth_methods`: the session cookie, an `Authorization: Bearer` access token,
+/// or a personal access token in the `PRIVATE-TOKEN` header.
+///
+/// usage: `middleware::from_extractor_with_state::<AuthGuard, _>(state)`
+pub struct AuthGuard;
+
+impl<S> FromRequestParts<S> for AuthGuard
+where
+    Arc<AppState>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = HttpException;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = config::Config::global();
+        let app_state = Arc::<AppState>::from_ref(state);
+
+        // remember why the first presented credentials were rejected
+        let mut rejection = None;
+        for method in config.auth_methods() {
+            let result = match method {
+                AuthMethod::Cookie => match cookie_token(parts, state).await {
+                    Some(token) => super::authorize(&app_state, &token).await,
+                    None => continue,
+                },
+                AuthMethod::Bearer => match bearer_token(parts) {
+                    Some(token) => super::authorize(&app_state, &token).await,
+                    None => continue,
+                },
+                AuthMethod::ApiToken => match header_value(parts, api_token::HEADER) {
+                    Some(token) => {
+                        super::authorize_api_token(&app_state, &token, &parts.method).await
+                    }
+                    None => continue,
+                },
+            };
+
+            match result {
+                Ok(claims) => {
+                    parts.extensions.insert(claims);
+                    return Ok(Self);
+                }
+                Err(err) => {
+                    rejection.get_or_insert(err);
+                }
+            }
+        }
+
+        Err(rejection.unwrap_or(HttpException::UnauthorizedException(None)))
+    }
+}
+
+async fn cookie_token<S>(parts: &mut Parts, state: &S) -> Option<String>
+where
+    S: Send + Sync,
+{
+    let cookies = Cookies::from_request_parts(parts, state).await.ok()?;
+    cookies
+        .get(config::Config::global().app_auth_key())
+        .map(|c| c.value().to_string())
+}
+
+fn bearer_token(parts: &Parts) -> Option<String> {
+    header_value(parts, header::AUTHORIZATION.as_str())?
+        .strip_prefix("Bearer ")
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+fn header_value(parts: &Parts, name: &str) -> Option<String> {
+    parts
+        .headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
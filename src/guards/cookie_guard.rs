@@ -1,34 +0,0 @@
-use anyhow::Result;
-use axum::{
-    extract::FromRequestParts,
-    http::{request::Parts, StatusCode},
-};
-use tower_cookies::Cookies;
-
-use crate::core::config;
-
-pub struct CookieGuard;
-
-impl<S> FromRequestParts<S> for CookieGuard
-where
-    S: Send + Sync,
-{
-    type Rejection = (StatusCode, &'static str);
-
-    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        let config = config::Config::global();
-        let cookies = Cookies::from_request_parts(parts, state).await?;
-        let cookie = cookies
-            .get(config.app_auth_key())
-            .map(|c| c.value().to_string())
-            .ok_or((StatusCode::UNAUTHORIZED, "Unauthorized"))?;
-
-        let claims = super::jwt_decode(&cookie, config.jwt_keys().decoding()).map_err(|err| {
-            tracing::error!(%err);
-            (StatusCode::UNAUTHORIZED, "Unauthorized")
-        })?;
-
-        parts.extensions.insert(claims);
-        Ok(Self)
-    }
-}
@@ -1,23 +1,50 @@
+use crate::{
+    core::{config, exception::HttpException, state::AppState},
+    services::{
+        api_token, jwt_keys,
+        rbac::{self, Access},
+        session, token_denylist,
+    },
+};
+use anyhow::anyhow;
 use axum::{
     extract::FromRequestParts,
-    http::{request::Parts, StatusCode},
+    http::{request::Parts, Method},
 };
-use jsonwebtoken::{decode, encode, errors::Error, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{decode, decode_header, errors::Error, Validation};
 use serde::{Deserialize, Serialize};
 use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
 
-mod cookie_guard;
-mod jwt_guard;
+mod auth_guard;
+mod permission_guard;
+mod verified_guard;
 
-pub use cookie_guard::*;
+pub use auth_guard::*;
+pub use permission_guard::*;
+pub use verified_guard::*;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Claims {
+    pub iss: String,
+    /// id of the user, a string as the spec requires
+    #[serde(rename = "sub", with = "jwt_subject")]
     pub user_id: i32,
+    pub aud: String,
+    /// unique id of the token, used to revoke it on its own
+    pub jti: String,
+    /// id of the login session the token belongs to
+    pub sid: String,
     #[serde(with = "jwt_numeric_date")]
     pub iat: OffsetDateTime,
     #[serde(with = "jwt_numeric_date")]
     pub exp: OffsetDateTime,
+    /// roles and permissions at the time the token was issued
+    #[serde(flatten)]
+    pub access: Access,
+    /// admin acting as the user in an impersonation session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator_id: Option<i32>,
 }
 
 /// https://github.com/Keats/jsonwebtoken/blob/master/examples/custom_time.rs
@@ -26,7 +53,7 @@ impl Claims {
     /// again, this function must be used for construction. `OffsetDateTime` contains a microsecond
     /// field but JWT timestamps are defined as UNIX timestamps (seconds). This function normalizes
     /// the timestamps.
-    pub fn new(user_id: i32, iat: OffsetDateTime, exp: OffsetDateTime) -> Self {
+    pub fn new(user_id: i32, sid: String, iat: OffsetDateTime, exp: OffsetDateTime) -> Self {
         // normalize the timestamps by stripping of microseconds
         let iat = iat
             .date()
@@ -39,7 +66,46 @@ impl Claims {
             .unwrap()
             .assume_utc();
 
-        Self { user_id, iat, exp }
+        let config = config::Config::global();
+        Self {
+            iss: config.jwt_issuer().to_string(),
+            user_id,
+            aud: config.jwt_audience().to_string(),
+            jti: Uuid::new_v4().to_string(),
+            sid,
+            iat,
+            exp,
+            access: Access::default(),
+            impersonator_id: None,
+        }
+    }
+
+    pub fn with_access(mut self, access: Access) -> Self {
+        self.access = access;
+        self
+    }
+
+    pub fn with_impersonator(mut self, impersonator_id: Option<i32>) -> Self {
+        self.impersonator_id = impersonator_id;
+        self
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.access.has_role(role)
+    }
+
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.access.has_permission(permission)
+    }
+
+    /// Whether the request was authenticated with a personal access token instead of a session
+    pub fn is_api_token(&self) -> bool {
+        self.sid.starts_with(api_token::TOKEN_PREFIX)
+    }
+
+    /// Whether an admin is acting as the user, credentials cannot be changed then
+    pub fn is_impersonated(&self) -> bool {
+        self.impersonator_id.is_some()
     }
 }
 
@@ -47,31 +113,127 @@ impl<S> FromRequestParts<S> for Claims
 where
     S: Send + Sync,
 {
-    type Rejection = (StatusCode, &'static str);
+    type Rejection = HttpException;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
         let claims = parts
             .extensions
             .get::<Claims>()
-            .ok_or((StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+            .ok_or(HttpException::UnauthorizedException(None))?;
 
         Ok(claims.clone())
     }
 }
 
-pub fn jwt_encode(user_id: i32, encoding: &EncodingKey) -> anyhow::Result<String, Error> {
+/// Sign an access token with the current signing key
+pub fn jwt_encode(
+    user_id: i32,
+    sid: &str,
+    access: Access,
+    impersonator_id: Option<i32>,
+) -> anyhow::Result<String, Error> {
     let iat = OffsetDateTime::now_utc();
-    let exp = iat + Duration::days(1);
-    let claims = Claims::new(user_id, iat, exp);
+    let exp = iat + Duration::seconds(config::Config::global().access_token_ttl());
+    let claims = Claims::new(user_id, sid.to_string(), iat, exp)
+        .with_access(access)
+        .with_impersonator(impersonator_id);
 
-    encode(&Header::default(), &claims, encoding)
+    jwt_keys::current().encode(&claims)
 }
 
-pub fn jwt_decode(token: &str, decoding: &DecodingKey) -> anyhow::Result<Claims> {
-    let decoded = decode::<Claims>(token, decoding, &Validation::default())?;
+/// Verify an access token with the key named by its `kid`, it must be issued by and for
+/// this app. Revoked tokens are checked separately since that takes a round trip to redis.
+pub fn jwt_decode(token: &str) -> anyhow::Result<Claims> {
+    let config = config::Config::global();
+    let keys = jwt_keys::current();
+    let header = decode_header(token)?;
+    let decoding = keys
+        .decoding(header.kid.as_deref())
+        .ok_or_else(|| anyhow!("unknown key id {:?}", header.kid))?;
+
+    let mut validation = Validation::new(keys.algorithm());
+    validation.leeway = config.jwt_leeway();
+    validation.set_issuer(&[config.jwt_issuer()]);
+    validation.set_audience(&[config.jwt_audience()]);
+    validation.set_required_spec_claims(&["exp", "iat", "iss", "aud", "sub", "jti"]);
+    let decoded = decode::<Claims>(token, decoding, &validation)?;
     Ok(decoded.claims)
 }
 
+/// Decode an access token and make sure neither it nor its session has been revoked
+pub(crate) async fn authorize(state: &AppState, token: &str) -> Result<Claims, HttpException> {
+    let claims = jwt_decode(token).map_err(|err| {
+        tracing::error!(%err);
+        HttpException::UnauthorizedException(None)
+    })?;
+    if token_denylist::is_revoked(&state.redis_pool, &claims.jti).await? {
+        return Err(HttpException::UnauthorizedException(Some(
+            "Token has been revoked".to_string(),
+        )));
+    }
+
+    let mut session = session::find(&state.redis_pool, &claims.sid)
+        .await?
+        .filter(|session| session.user_id == claims.user_id)
+        .ok_or(HttpException::UnauthorizedException(Some(
+            "Session has been revoked".to_string(),
+        )))?;
+    session::touch(&state.redis_pool, &mut session).await?;
+
+    Ok(claims)
+}
+
+/// Authenticate a personal access token and check its scopes allow the request method
+async fn authorize_api_token(
+    state: &AppState,
+    token: &str,
+    method: &Method,
+) -> Result<Claims, HttpException> {
+    let token = api_token::authenticate(&state.db, token).await?;
+    if !api_token::allows(&token, method) {
+        return Err(HttpException::ForbiddenException(Some(
+            "Insufficient token scope".to_string(),
+        )));
+    }
+
+    let iat = OffsetDateTime::now_utc();
+    let exp = token
+        .expires_at
+        .and_then(|expires_at| OffsetDateTime::from_unix_timestamp(expires_at.timestamp()).ok())
+        .unwrap_or(iat + Duration::seconds(config::Config::global().access_token_ttl()));
+
+    let access = rbac::load_access(&state.db, token.user_id).await?;
+
+    Ok(Claims::new(
+        token.user_id,
+        format!("{}{}", api_token::TOKEN_PREFIX, token.id),
+        iat,
+        exp,
+    )
+    .with_access(access))
+}
+
+mod jwt_subject {
+    //! The `sub` claim is a string (RFC 7519 section 4.1.2) holding the user id
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(user_id: &i32, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&user_id.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<i32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(|_| serde::de::Error::custom("invalid subject"))
+    }
+}
+
 mod jwt_numeric_date {
     //! Custom serialization of OffsetDateTime to conform with the JWT spec (RFC 7519 section 2, "Numeric Date")
     use serde::{self, Deserialize, Deserializer, Serializer};
@@ -95,3 +257,30 @@ mod jwt_numeric_date {
             .map_err(|_| serde::de::Error::custom("invalid Unix timestamp value"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claims_subject() {
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let claims = Claims {
+            iss: "http://127.0.0.1:3000".to_string(),
+            user_id: 42,
+            aud: "http://127.0.0.1:3000".to_string(),
+            jti: "jti".to_string(),
+            sid: "sid".to_string(),
+            iat: now,
+            exp: now + Duration::minutes(15),
+            access: Access::default(),
+            impersonator_id: None,
+        };
+
+        let value = serde_json::to_value(&claims).unwrap();
+        assert_eq!(value["sub"], "42");
+        assert!(value.get("user_id").is_none());
+        assert!(value.get("impersonator_id").is_none());
+        assert_eq!(serde_json::from_value::<Claims>(value).unwrap(), claims);
+    }
+}
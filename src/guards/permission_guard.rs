@@ -0,0 +1,93 @@
+use axum::{extract::FromRequestParts, http::request::Parts};
+use std::marker::PhantomData;
+
+use crate::{
+    core::exception::HttpException,
+    services::rbac::{self, permissions},
+};
+
+use super::Claims;
+
+/// A permission that can be required with `RequirePermission`
+pub trait Permission {
+    const NAME: &'static str;
+}
+
+macro_rules! permission {
+    ($name:ident, $value:expr) => {
+        pub struct $name;
+
+        impl Permission for $name {
+            const NAME: &'static str = $value;
+        }
+    };
+}
+
+permission!(UsersRead, permissions::USERS_READ);
+permission!(UsersWrite, permissions::USERS_WRITE);
+permission!(UsersDelete, permissions::USERS_DELETE);
+permission!(AuditLogsRead, permissions::AUDIT_LOGS_READ);
+
+/// A role that can be required with `RequireRole`
+pub trait RoleName {
+    const NAME: &'static str;
+}
+
+pub struct Admin;
+
+impl RoleName for Admin {
+    const NAME: &'static str = rbac::ROLE_ADMIN;
+}
+
+/// Rejects the request with 403 unless the caller holds the permission `P`.
+/// Must run after `AuthGuard`.
+///
+/// usage: as a handler argument `_: RequirePermission<UsersDelete>`,
+/// or as a layer `middleware::from_extractor::<RequirePermission<UsersDelete>>()`
+pub struct RequirePermission<P>(PhantomData<P>);
+
+impl<S, P> FromRequestParts<S> for RequirePermission<P>
+where
+    P: Permission,
+    S: Send + Sync,
+{
+    type Rejection = HttpException;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let claims = Claims::from_request_parts(parts, state).await?;
+        if !claims.has_permission(P::NAME) {
+            return Err(HttpException::ForbiddenException(Some(format!(
+                "Missing permission {}",
+                P::NAME
+            ))));
+        }
+
+        Ok(Self(PhantomData))
+    }
+}
+
+/// Rejects the request with 403 unless the caller holds the role `R`.
+/// Must run after `AuthGuard`.
+///
+/// usage: as a layer `middleware::from_extractor::<RequireRole<Admin>>()`
+pub struct RequireRole<R>(PhantomData<R>);
+
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+    R: RoleName,
+    S: Send + Sync,
+{
+    type Rejection = HttpException;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let claims = Claims::from_request_parts(parts, state).await?;
+        if !claims.has_role(R::NAME) {
+            return Err(HttpException::ForbiddenException(Some(format!(
+                "Missing role {}",
+                R::NAME
+            ))));
+        }
+
+        Ok(Self(PhantomData))
+    }
+}
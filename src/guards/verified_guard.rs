@@ -0,0 +1,49 @@
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use entity::{prelude::User, soft_delete::SoftDelete, user};
+use sea_orm::QuerySelect;
+use std::sync::Arc;
+
+use crate::core::{config, exception::HttpException, state::AppState};
+
+use super::Claims;
+
+/// Rejects the request with 403 when `Config::require_verified_email` is on and the caller
+/// has not verified their email yet. Must run after `AuthGuard`.
+///
+/// usage: `middleware::from_extractor_with_state::<VerifiedGuard, _>(state)`
+pub struct VerifiedGuard;
+
+impl<S> FromRequestParts<S> for VerifiedGuard
+where
+    Arc<AppState>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = HttpException;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if !config::Config::global().require_verified_email() {
+            return Ok(Self);
+        }
+
+        let claims = Claims::from_request_parts(parts, state).await?;
+        let app_state = Arc::<AppState>::from_ref(state);
+        let verified_at: Option<Option<chrono::DateTime<chrono::Utc>>> =
+            User::find_active_by_id(claims.user_id)
+                .select_only()
+                .column(user::Column::EmailVerifiedAt)
+                .into_tuple()
+                .one(&app_state.db)
+                .await?;
+
+        match verified_at {
+            Some(Some(_)) => Ok(Self),
+            Some(None) => Err(HttpException::ForbiddenException(Some(
+                "Email address has not been verified".to_string(),
+            ))),
+            None => Err(HttpException::UnauthorizedException(None)),
+        }
+    }
+}
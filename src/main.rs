@@ -6,6 +6,7 @@ mod events;
 mod extractors;
 mod guards;
 mod routes;
+mod services;
 mod utils;
 
 fn main() {
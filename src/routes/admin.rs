@@ -0,0 +1,827 @@
+use super::{
+    user::{AuthPayload, SessionSchema},
+    HttpResponse, JsonResponse, Page,
+};
+use crate::{
+    core::{exception::HttpException, state},
+    dtos::admin_dtos::{
+        AdminUserParam, DisableUserDto, ListAuditLogsDto, ListUsersDto, RevokeTokenDto,
+    },
+    extractors::{Body, ClientInfo, Param, Query, DEFAULT_PER_PAGE},
+    guards::{
+        jwt_decode, Admin, AuditLogsRead, Claims, RequirePermission, RequireRole, UsersDelete,
+        UsersRead, UsersWrite,
+    },
+    http_exception, http_exception_or,
+    services::{
+        audit::{self, actions},
+        personal_data,
+        rbac::{self, Access},
+        session, token_denylist,
+    },
+};
+use axum::{extract::State, middleware};
+use axum_macros::debug_handler;
+use entity::{
+    audit_log, post,
+    prelude::{AuditLog, Post, User},
+    role,
+    soft_delete::SoftDelete,
+    user, user_role,
+};
+use sea_orm::{
+    sea_query::{extension::postgres::PgExpr, Expr, Query as SelectQuery},
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(list_users))
+        .routes(routes!(get_user))
+        .routes(routes!(disable_user))
+        .routes(routes!(enable_user))
+        .routes(routes!(logout_user))
+        .routes(routes!(impersonate_user))
+        .routes(routes!(restore_user))
+        .routes(routes!(erase_user))
+        .routes(routes!(revoke_token))
+        .routes(routes!(list_audit_logs))
+        .route_layer(middleware::from_extractor::<RequireRole<Admin>>());
+
+    OpenApiRouter::new().nest("/admin", router)
+}
+
+/// List users
+///
+/// List users page by page, optionally searched by name or email and filtered by role,
+/// status and email verification. Soft-deleted users are only listed with `deleted=true`.
+#[utoipa::path(
+  get,
+  path = "/users",
+  params(
+    ("page" = Option<u64>, Query, description = "Page number starting at 1, at most 10000"),
+    ("per_page" = Option<u64>, Query, description = "Page size, 20 by default, at most 100"),
+    ("q" = Option<String>, Query, description = "Part of the name or email"),
+    ("role" = Option<String>, Query, description = "Only users holding this role"),
+    ("disabled" = Option<bool>, Query, description = "Only disabled or enabled users"),
+    ("verified" = Option<bool>, Query, description = "Only users with or without a verified email"),
+    ("deleted" = Option<bool>, Query, description = "List soft-deleted users instead"),
+  ),
+  responses(
+    (status = 200, description = "List users successfully", body = JsonResponse<UserPageSchema>),
+    (status = 403, description = "Missing admin role or users:read permission"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::ADMIN_TAG
+)]
+#[debug_handler]
+async fn list_users(
+    State(state): State<Arc<state::AppState>>,
+    _: RequirePermission<UsersRead>,
+    claims: Claims,
+    client: ClientInfo,
+    Query(input): Query<ListUsersDto>,
+) -> Result<HttpResponse<Page<user::Model>>, HttpException> {
+    // reading user data is recorded like changing it
+    audit::record(
+        &state.db,
+        claims.user_id,
+        actions::USER_LIST,
+        None,
+        Some(json!({
+            "q": input.q,
+            "role": input.role,
+            "disabled": input.disabled,
+            "verified": input.verified,
+            "deleted": input.deleted,
+            "page": input.page,
+        })),
+        client.ip,
+    )
+    .await?;
+
+    let mut query = if input.deleted.unwrap_or(false) {
+        User::find_deleted()
+    } else {
+        User::find_active()
+    }
+    .order_by_asc(user::Column::Id);
+
+    if let Some(q) = input.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        let pattern = format!("%{q}%");
+        query = query.filter(
+            Condition::any()
+                .add(Expr::col((user::Entity, user::Column::Name)).ilike(&pattern))
+                .add(Expr::col((user::Entity, user::Column::Email)).ilike(&pattern)),
+        );
+    }
+    if let Some(role) = input.role {
+        query = query.filter(
+            user::Column::Id.in_subquery(
+                SelectQuery::select()
+                    .column((user_role::Entity, user_role::Column::UserId))
+                    .from(user_role::Entity)
+                    .inner_join(
+                        role::Entity,
+                        Expr::col((role::Entity, role::Column::Id))
+                            .equals((user_role::Entity, user_role::Column::RoleId)),
+                    )
+                    .and_where(Expr::col((role::Entity, role::Column::Name)).eq(role))
+                    .to_owned(),
+            ),
+        );
+    }
+    if let Some(disabled) = input.disabled {
+        query = query.filter(if disabled {
+            user::Column::DisabledAt.is_not_null()
+        } else {
+            user::Column::DisabledAt.is_null()
+        });
+    }
+    if let Some(verified) = input.verified {
+        query = query.filter(if verified {
+            user::Column::EmailVerifiedAt.is_not_null()
+        } else {
+            user::Column::EmailVerifiedAt.is_null()
+        });
+    }
+
+    let page = input.page.unwrap_or(1);
+    let per_page = input.per_page.unwrap_or(DEFAULT_PER_PAGE);
+    let paginator = query.paginate(&state.db, per_page);
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(page - 1).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(Page {
+            items,
+            total,
+            page: Some(page),
+            per_page,
+            next_cursor: None,
+        }),
+    })
+}
+
+/// View user
+///
+/// Get a user with their roles, permissions and active sessions.
+#[utoipa::path(
+  get,
+  path = "/users/{id}",
+  params(
+    ("id" = i32, Path, description = "User database id"),
+  ),
+  responses(
+    (status = 200, description = "Query user successfully", body = JsonResponse<AdminUserSchema>),
+    (status = 403, description = "Missing admin role or users:read permission"),
+    (status = 404, description = "User not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::ADMIN_TAG
+)]
+#[debug_handler]
+async fn get_user(
+    State(state): State<Arc<state::AppState>>,
+    _: RequirePermission<UsersRead>,
+    claims: Claims,
+    client: ClientInfo,
+    Param(input): Param<AdminUserParam>,
+) -> Result<HttpResponse<AdminUser>, HttpException> {
+    let user = find_user(&state, input.id).await?;
+    audit::record(
+        &state.db,
+        claims.user_id,
+        actions::USER_VIEW,
+        Some(user.id),
+        None,
+        client.ip,
+    )
+    .await?;
+    let access = rbac::load_access(&state.db, user.id).await?;
+    let sessions = session::list(&state.redis_pool, user.id)
+        .await?
+        .into_iter()
+        .map(|session| SessionSchema::new(session, &claims.sid))
+        .collect();
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(AdminUser {
+            user,
+            access,
+            sessions,
+        }),
+    })
+}
+
+/// Disable user
+///
+/// Block a user from logging in and log out all of their sessions.
+#[utoipa::path(
+  post,
+  path = "/users/{id}/disable",
+  request_body = DisableUserDto,
+  params(
+    ("id" = i32, Path, description = "User database id"),
+  ),
+  responses(
+    (status = 200, description = "User disabled successfully", body = JsonResponse<ProfileSchema>),
+    (status = 400, description = "User already disabled, or disabling yourself"),
+    (status = 403, description = "Missing admin role or users:write permission"),
+    (status = 404, description = "User not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::ADMIN_TAG
+)]
+#[debug_handler]
+async fn disable_user(
+    State(state): State<Arc<state::AppState>>,
+    _: RequirePermission<UsersWrite>,
+    claims: Claims,
+    client: ClientInfo,
+    Param(param): Param<AdminUserParam>,
+    Body(input): Body<DisableUserDto>,
+) -> Result<HttpResponse<user::Model>, HttpException> {
+    if param.id == claims.user_id {
+        http_exception!(BadRequestException, "You cannot disable yourself");
+    }
+    let user = find_user(&state, param.id).await?;
+    if user.disabled_at.is_some() {
+        http_exception!(
+            BadRequestException,
+            format!("The user {} is already disabled", param.id)
+        );
+    }
+
+    let txn = state.db.begin().await?;
+    let mut user = user.into_active_model();
+    user.disabled_at = Set(Some(chrono::Utc::now()));
+    let user = user.update(&txn).await?;
+    audit::record(
+        &txn,
+        claims.user_id,
+        actions::USER_DISABLE,
+        Some(user.id),
+        Some(json!({ "reason": input.reason })),
+        client.ip,
+    )
+    .await?;
+    txn.commit().await?;
+    session::terminate_all(&state, user.id).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("The user {} has been disabled", user.id)),
+        payload: Some(user),
+    })
+}
+
+/// Enable user
+///
+/// Allow a disabled user to log in again.
+#[utoipa::path(
+  post,
+  path = "/users/{id}/enable",
+  params(
+    ("id" = i32, Path, description = "User database id"),
+  ),
+  responses(
+    (status = 200, description = "User enabled successfully", body = JsonResponse<ProfileSchema>),
+    (status = 400, description = "User is not disabled"),
+    (status = 403, description = "Missing admin role or users:write permission"),
+    (status = 404, description = "User not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::ADMIN_TAG
+)]
+#[debug_handler]
+async fn enable_user(
+    State(state): State<Arc<state::AppState>>,
+    _: RequirePermission<UsersWrite>,
+    claims: Claims,
+    client: ClientInfo,
+    Param(param): Param<AdminUserParam>,
+) -> Result<HttpResponse<user::Model>, HttpException> {
+    let user = find_user(&state, param.id).await?;
+    if user.disabled_at.is_none() {
+        http_exception!(
+            BadRequestException,
+            format!("The user {} is not disabled", param.id)
+        );
+    }
+
+    let txn = state.db.begin().await?;
+    let mut user = user.into_active_model();
+    user.disabled_at = Set(None);
+    let user = user.update(&txn).await?;
+    audit::record(
+        &txn,
+        claims.user_id,
+        actions::USER_ENABLE,
+        Some(user.id),
+        None,
+        client.ip,
+    )
+    .await?;
+    txn.commit().await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("The user {} has been enabled", user.id)),
+        payload: Some(user),
+    })
+}
+
+/// Force logout
+///
+/// Log out every session of a user and disconnect their sockets.
+#[utoipa::path(
+  post,
+  path = "/users/{id}/logout",
+  params(
+    ("id" = i32, Path, description = "User database id"),
+  ),
+  responses(
+    (status = 200, description = "Sessions revoked successfully"),
+    (status = 403, description = "Missing admin role or users:write permission"),
+    (status = 404, description = "User not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::ADMIN_TAG
+)]
+#[debug_handler]
+async fn logout_user(
+    State(state): State<Arc<state::AppState>>,
+    _: RequirePermission<UsersWrite>,
+    claims: Claims,
+    client: ClientInfo,
+    Param(param): Param<AdminUserParam>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let user = find_user(&state, param.id).await?;
+    let sessions = session::list(&state.redis_pool, user.id).await?;
+    audit::record(
+        &state.db,
+        claims.user_id,
+        actions::USER_LOGOUT,
+        Some(user.id),
+        Some(json!({ "sessions": sessions.len() })),
+        client.ip,
+    )
+    .await?;
+    session::terminate(&state, &sessions).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!(
+            "{} sessions of the user {} have been revoked",
+            sessions.len(),
+            user.id
+        )),
+        payload: None,
+    })
+}
+
+/// Impersonate user
+///
+/// Start a session as another user to reproduce what they see. The credentials are only
+/// returned in the body so the cookies of the admin are left alone. The session is listed
+/// among the sessions of the user with the id of the admin, its access token carries the id
+/// as `impersonator_id`. It cannot be refreshed, and the password, email, two-factor
+/// authentication, access tokens and identities of the user cannot be changed with it.
+#[utoipa::path(
+  post,
+  path = "/users/{id}/impersonate",
+  params(
+    ("id" = i32, Path, description = "User database id"),
+  ),
+  responses(
+    (status = 200, description = "Impersonation session started", body = JsonResponse<AuthSchema>),
+    (status = 400, description = "User is disabled, deleted, an admin, or yourself"),
+    (status = 403, description = "Missing admin role, users:write permission or called with a personal access token"),
+    (status = 404, description = "User not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::ADMIN_TAG
+)]
+#[debug_handler]
+async fn impersonate_user(
+    State(state): State<Arc<state::AppState>>,
+    _: RequirePermission<UsersWrite>,
+    claims: Claims,
+    client: ClientInfo,
+    Param(param): Param<AdminUserParam>,
+) -> Result<HttpResponse<AuthPayload>, HttpException> {
+    if claims.is_api_token() {
+        http_exception!(
+            ForbiddenException,
+            "Personal access tokens cannot impersonate users"
+        );
+    }
+    if param.id == claims.user_id {
+        http_exception!(BadRequestException, "You cannot impersonate yourself");
+    }
+    let user = find_user(&state, param.id).await?;
+    if user.disabled_at.is_some() || user.deleted_at.is_some() {
+        http_exception!(
+            BadRequestException,
+            format!("The user {} is disabled or deleted", param.id)
+        );
+    }
+    if rbac::load_access(&state.db, user.id)
+        .await?
+        .has_role(rbac::ROLE_ADMIN)
+    {
+        http_exception!(BadRequestException, "Admins cannot be impersonated");
+    }
+
+    let sid = Uuid::new_v4().to_string();
+    audit::record(
+        &state.db,
+        claims.user_id,
+        actions::USER_IMPERSONATE,
+        Some(user.id),
+        Some(json!({ "sid": sid })),
+        client.ip.clone(),
+    )
+    .await?;
+    let (_, tokens) = session::impersonate(&state, sid, user.id, claims.user_id, &client).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(AuthPayload { user, tokens }),
+    })
+}
+
+/// Restore user
+///
+/// Bring back a soft-deleted user together with the posts deleted along with it.
+#[utoipa::path(
+  post,
+  path = "/users/{id}/restore",
+  params(
+    ("id" = i32, Path, description = "User database id"),
+  ),
+  responses(
+    (status = 200, description = "User restored successfully", body = JsonResponse<ProfileSchema>),
+    (status = 400, description = "User is not deleted"),
+    (status = 403, description = "Missing admin role or users:write permission"),
+    (status = 404, description = "User not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::ADMIN_TAG
+)]
+#[debug_handler]
+async fn restore_user(
+    State(state): State<Arc<state::AppState>>,
+    _: RequirePermission<UsersWrite>,
+    claims: Claims,
+    client: ClientInfo,
+    Param(param): Param<AdminUserParam>,
+) -> Result<HttpResponse<user::Model>, HttpException> {
+    let user = find_user(&state, param.id).await?;
+    let Some(deleted_at) = user.deleted_at else {
+        http_exception!(
+            BadRequestException,
+            format!("The user {} is not deleted", param.id)
+        );
+    };
+
+    let txn = state.db.begin().await?;
+    let mut user = user.into_active_model();
+    user.deleted_at = Set(None);
+    let user = user.update(&txn).await?;
+    let posts = Post::update_many()
+        .col_expr(
+            post::Column::DeletedAt,
+            Expr::value(None::<chrono::DateTime<chrono::Utc>>),
+        )
+        .filter(post::Column::UserId.eq(user.id))
+        .filter(post::Column::DeletedAt.eq(deleted_at))
+        .exec(&txn)
+        .await?;
+    audit::record(
+        &txn,
+        claims.user_id,
+        actions::USER_RESTORE,
+        Some(user.id),
+        Some(json!({ "posts": posts.rows_affected })),
+        client.ip,
+    )
+    .await?;
+    txn.commit().await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("The user {} has been restored", user.id)),
+        payload: Some(user),
+    })
+}
+
+/// Erase user
+///
+/// Permanently erase the personal data of a user on their request. Posts, uploads and access
+/// tokens are deleted, the account is anonymized and every session is logged out.
+#[utoipa::path(
+  post,
+  path = "/users/{id}/erase",
+  params(
+    ("id" = i32, Path, description = "User database id"),
+  ),
+  responses(
+    (status = 200, description = "User erased successfully"),
+    (status = 400, description = "Erasing yourself"),
+    (status = 403, description = "Missing admin role or users:delete permission"),
+    (status = 404, description = "User not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::ADMIN_TAG
+)]
+#[debug_handler]
+async fn erase_user(
+    State(state): State<Arc<state::AppState>>,
+    _: RequirePermission<UsersDelete>,
+    claims: Claims,
+    client: ClientInfo,
+    Param(param): Param<AdminUserParam>,
+) -> Result<HttpResponse<()>, HttpException> {
+    if param.id == claims.user_id {
+        http_exception!(
+            BadRequestException,
+            "Erase your own account from /user/me/erase"
+        );
+    }
+    let user = find_user(&state, param.id).await?;
+    let user_id = user.id;
+
+    let txn = state.db.begin().await?;
+    let erased = personal_data::erase_records(&txn, user).await?;
+    audit::record(
+        &txn,
+        claims.user_id,
+        actions::USER_ERASE,
+        Some(user_id),
+        None,
+        client.ip,
+    )
+    .await?;
+    txn.commit().await?;
+    personal_data::forget(&state, erased).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("The user {} has been erased", user_id)),
+        payload: None,
+    })
+}
+
+/// Revoke access token
+///
+/// Refuse a single access token, e.g. a leaked one, until it expires. The session it
+/// belongs to stays logged in with its next tokens.
+#[utoipa::path(
+  post,
+  path = "/tokens/revoke",
+  request_body = RevokeTokenDto,
+  responses(
+    (status = 200, description = "Token revoked successfully"),
+    (status = 400, description = "Invalid or expired token"),
+    (status = 403, description = "Missing admin role or users:write permission"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::ADMIN_TAG
+)]
+#[debug_handler]
+async fn revoke_token(
+    State(state): State<Arc<state::AppState>>,
+    _: RequirePermission<UsersWrite>,
+    claims: Claims,
+    client: ClientInfo,
+    Body(input): Body<RevokeTokenDto>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let Ok(revoked) = jwt_decode(&input.token) else {
+        http_exception!(BadRequestException, "Invalid or expired token");
+    };
+    token_denylist::revoke(&state.redis_pool, &revoked).await?;
+    audit::record(
+        &state.db,
+        claims.user_id,
+        actions::TOKEN_REVOKE,
+        Some(revoked.user_id),
+        Some(json!({ "jti": revoked.jti, "sid": revoked.sid })),
+        client.ip,
+    )
+    .await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("The token {} has been revoked", revoked.jti)),
+        payload: None,
+    })
+}
+
+/// List audit logs
+///
+/// List the audit trail of administrative actions, newest first.
+#[utoipa::path(
+  get,
+  path = "/audit-logs",
+  params(
+    ("page" = Option<u64>, Query, description = "Page number starting at 1, at most 10000"),
+    ("per_page" = Option<u64>, Query, description = "Page size, 20 by default, at most 100"),
+    ("actor_id" = Option<i32>, Query, description = "Only actions performed by this user"),
+    ("target_user_id" = Option<i32>, Query, description = "Only actions performed on this user"),
+    ("action" = Option<String>, Query, description = "Only this action, e.g. user.disable"),
+  ),
+  responses(
+    (status = 200, description = "List audit logs successfully", body = JsonResponse<AuditLogPageSchema>),
+    (status = 403, description = "Missing admin role or audit_logs:read permission"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::ADMIN_TAG
+)]
+#[debug_handler]
+async fn list_audit_logs(
+    State(state): State<Arc<state::AppState>>,
+    _: RequirePermission<AuditLogsRead>,
+    claims: Claims,
+    client: ClientInfo,
+    Query(input): Query<ListAuditLogsDto>,
+) -> Result<HttpResponse<Page<audit_log::Model>>, HttpException> {
+    audit::record(
+        &state.db,
+        claims.user_id,
+        actions::AUDIT_LOG_LIST,
+        input.target_user_id,
+        Some(json!({
+            "actor_id": input.actor_id,
+            "action": input.action,
+            "page": input.page,
+        })),
+        client.ip,
+    )
+    .await?;
+
+    let mut query = AuditLog::find().order_by_desc(audit_log::Column::Id);
+    if let Some(actor_id) = input.actor_id {
+        query = query.filter(audit_log::Column::ActorId.eq(actor_id));
+    }
+    if let Some(target_user_id) = input.target_user_id {
+        query = query.filter(audit_log::Column::TargetUserId.eq(target_user_id));
+    }
+    if let Some(action) = input.action {
+        query = query.filter(audit_log::Column::Action.eq(action));
+    }
+
+    let page = input.page.unwrap_or(1);
+    let per_page = input.per_page.unwrap_or(DEFAULT_PER_PAGE);
+    let paginator = query.paginate(&state.db, per_page);
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(page - 1).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(Page {
+            items,
+            total,
+            page: Some(page),
+            per_page,
+            next_cursor: None,
+        }),
+    })
+}
+
+async fn find_user(state: &state::AppState, id: i32) -> Result<user::Model, HttpException> {
+    let user = http_exception_or!(
+        User::find_with_deleted_by_id(id).one(&state.db).await?,
+        NotFoundException,
+        format!("No user found with id {}", id)
+    );
+
+    Ok(user)
+}
+
+#[derive(Serialize)]
+struct AdminUser {
+    #[serde(flatten)]
+    user: user::Model,
+    #[serde(flatten)]
+    access: Access,
+    sessions: Vec<SessionSchema>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ProfileSchema {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub email_verified_at: Option<String>,
+    pub totp_enabled_at: Option<String>,
+    pub disabled_at: Option<String>,
+    pub deleted_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct UserPageSchema {
+    pub items: Vec<ProfileSchema>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct AdminUserSchema {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub email_verified_at: Option<String>,
+    pub totp_enabled_at: Option<String>,
+    pub disabled_at: Option<String>,
+    pub deleted_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    pub sessions: Vec<SessionSchema>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct AuthSchema {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct AuditLogSchema {
+    pub id: i32,
+    pub actor_id: Option<i32>,
+    #[schema(example = "user.disable")]
+    pub action: String,
+    pub target_user_id: Option<i32>,
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+    pub ip: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct AuditLogPageSchema {
+    pub items: Vec<AuditLogSchema>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+}
@@ -0,0 +1,376 @@
+use super::{
+    post::{find_readable_post, AuthorSummary},
+    HttpResponse, JsonResponse, Page,
+};
+use crate::{
+    core::{exception::HttpException, state},
+    dtos::{
+        comment_dtos::{CreateCommentDto, ListCommentsDto, QueryCommentDto, UpdateCommentDto},
+        post_dtos::QueryPostDto,
+    },
+    events,
+    extractors::{Body, Pagination, Param, Query},
+    guards::Claims,
+    http_exception, http_exception_or,
+};
+use axum::extract::State;
+use axum_macros::debug_handler;
+use entity::{
+    comment, post,
+    prelude::{Comment, Post, User},
+    soft_delete::SoftDelete,
+};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel,
+    LoaderTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(get_all, create_one))
+        .routes(routes!(update_one, delete_one));
+
+    OpenApiRouter::new().nest("/post/{id}/comments", router)
+}
+
+/// List comments of a Post
+///
+/// List one level of the comment threads of a Post the current user can read, oldest first.
+/// Without `parent_id` the comments at the top of the threads are listed, each with how many
+/// replies it has, pass the id of a comment to list its replies.
+#[utoipa::path(
+  get,
+  path = "",
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+    ("page" = Option<u64>, Query, description = "Page number starting at 1, at most 10000"),
+    ("per_page" = Option<u64>, Query, description = "Page size, 20 by default, at most 100"),
+    ("parent_id" = Option<i32>, Query, description = "Comment whose replies are listed"),
+  ),
+  responses(
+    (status = 200, description = "List comments successfully", body = JsonResponse<CommentPageSchema>),
+    (status = 400, description = "Paged by cursor"),
+    (status = 404, description = "Post not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn get_all(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryPostDto>,
+    pagination: Pagination,
+    Query(input): Query<ListCommentsDto>,
+) -> Result<HttpResponse<Page<CommentView>>, HttpException> {
+    let Pagination::Offset { page, per_page } = pagination else {
+        http_exception!(BadRequestException, "Comments are paged by number");
+    };
+    let post = find_readable_post(&state.db, param.id, &claims).await?;
+
+    let query = Comment::find()
+        .filter(comment::Column::PostId.eq(post.id))
+        .filter(match input.parent_id {
+            Some(parent_id) => comment::Column::ParentId.eq(parent_id),
+            None => comment::Column::ParentId.is_null(),
+        });
+    let total = query.clone().count(&state.db).await?;
+    let comments = query
+        .order_by_asc(comment::Column::CreatedAt)
+        .order_by_asc(comment::Column::Id)
+        .paginate(&state.db, per_page)
+        .fetch_page(page - 1)
+        .await?;
+    let items = CommentView::load(&state.db, comments).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(Page {
+            items,
+            total,
+            page: Some(page),
+            per_page,
+            next_cursor: None,
+        }),
+    })
+}
+
+/// Create comment on a Post
+///
+/// Comment on a Post the current user can read, or reply to one of its comments with
+/// `parentId`. The owner of the Post receives a `comment:created` socket event with the
+/// comment.
+#[utoipa::path(
+  post,
+  path = "",
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+  ),
+  request_body = CreateCommentDto,
+  responses(
+    (status = 200, description = "Create comment successfully", body = JsonResponse<CommentSchema>),
+    (status = 404, description = "Post or parent comment not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn create_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryPostDto>,
+    Body(input): Body<CreateCommentDto>,
+) -> Result<HttpResponse<CommentView>, HttpException> {
+    let post = find_readable_post(&state.db, param.id, &claims).await?;
+    if let Some(parent_id) = input.parent_id {
+        http_exception_or!(
+            Comment::find_by_id(parent_id)
+                .filter(comment::Column::PostId.eq(post.id))
+                .one(&state.db)
+                .await?,
+            NotFoundException,
+            format!("No comment found with id {} on post {}", parent_id, post.id)
+        );
+    }
+
+    let comment = comment::ActiveModel {
+        text: Set(input.text),
+        post_id: Set(post.id),
+        user_id: Set(claims.user_id),
+        parent_id: Set(input.parent_id),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await?;
+    let author = comment.find_related(User).one(&state.db).await?;
+    let comment = CommentView {
+        comment,
+        author: author.map(AuthorSummary::from),
+        replies: 0,
+    };
+
+    if post.user_id != claims.user_id {
+        events::emit_to_user(
+            &state.io,
+            &state.clients,
+            post.user_id,
+            events::COMMENT_CREATED,
+            &comment,
+        );
+    }
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(comment),
+    })
+}
+
+/// Edit comment
+///
+/// Change the text of a comment written by the current user.
+#[utoipa::path(
+  patch,
+  path = "/{comment_id}",
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+    ("comment_id" = i32, Path, description = "Comment database id"),
+  ),
+  request_body = UpdateCommentDto,
+  responses(
+    (status = 200, description = "Edit comment successfully", body = JsonResponse<CommentSchema>),
+    (status = 403, description = "Comment written by another user"),
+    (status = 404, description = "Comment not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn update_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryCommentDto>,
+    Body(input): Body<UpdateCommentDto>,
+) -> Result<HttpResponse<CommentView>, HttpException> {
+    let (_, comment) = find_comment(&state.db, &param).await?;
+    if comment.user_id != claims.user_id {
+        http_exception!(
+            ForbiddenException,
+            format!("You are not allowed to edit comment {}", comment.id)
+        );
+    }
+
+    let mut comment = comment.into_active_model();
+    comment.text = Set(input.text);
+    let comment = comment.update(&state.db).await?;
+    let comment = CommentView::load(&state.db, vec![comment])
+        .await?
+        .pop()
+        .expect("one view per comment");
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(comment),
+    })
+}
+
+/// Delete comment
+///
+/// Delete a comment and its replies. Comments can be deleted by who wrote them and by the
+/// owner of the Post.
+#[utoipa::path(
+  delete,
+  path = "/{comment_id}",
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+    ("comment_id" = i32, Path, description = "Comment database id"),
+  ),
+  responses(
+    (status = 200, description = "Comment delete done successfully"),
+    (status = 403, description = "Neither the author of the comment nor the owner of the post"),
+    (status = 404, description = "Comment not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn delete_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryCommentDto>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let (post, comment) = find_comment(&state.db, &param).await?;
+    if comment.user_id != claims.user_id && post.user_id != claims.user_id {
+        http_exception!(
+            ForbiddenException,
+            format!("You are not allowed to delete comment {}", comment.id)
+        );
+    }
+
+    // the replies go with it through the foreign key
+    comment.delete(&state.db).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!(
+            "The comment {} has been successfully deleted",
+            param.comment_id
+        )),
+        payload: None,
+    })
+}
+
+/// Load a comment with the post it is on.
+async fn find_comment<C>(
+    db: &C,
+    param: &QueryCommentDto,
+) -> Result<(post::Model, comment::Model), HttpException>
+where
+    C: ConnectionTrait,
+{
+    let post = http_exception_or!(
+        Post::find_active_by_id(param.id).one(db).await?,
+        NotFoundException,
+        format!("No post found with id {}", param.id)
+    );
+    let comment = http_exception_or!(
+        Comment::find_by_id(param.comment_id)
+            .filter(comment::Column::PostId.eq(post.id))
+            .one(db)
+            .await?,
+        NotFoundException,
+        format!(
+            "No comment found with id {} on post {}",
+            param.comment_id, post.id
+        )
+    );
+
+    Ok((post, comment))
+}
+
+/// A comment with who wrote it and how many replies it has
+#[derive(Serialize)]
+struct CommentView {
+    #[serde(flatten)]
+    comment: comment::Model,
+    author: Option<AuthorSummary>,
+    replies: i64,
+}
+
+impl CommentView {
+    async fn load<C>(db: &C, comments: Vec<comment::Model>) -> Result<Vec<Self>, HttpException>
+    where
+        C: ConnectionTrait,
+    {
+        let authors = comments.load_one(User, db).await?;
+        let ids: Vec<i32> = comments.iter().map(|comment| comment.id).collect();
+        let replies: HashMap<i32, i64> = if ids.is_empty() {
+            HashMap::new()
+        } else {
+            Comment::find()
+                .select_only()
+                .column(comment::Column::ParentId)
+                .column_as(Expr::col(comment::Column::Id).count(), "replies")
+                .filter(comment::Column::ParentId.is_in(ids))
+                .group_by(comment::Column::ParentId)
+                .into_tuple::<(i32, i64)>()
+                .all(db)
+                .await?
+                .into_iter()
+                .collect()
+        };
+
+        Ok(comments
+            .into_iter()
+            .zip(authors)
+            .map(|(comment, author)| Self {
+                replies: replies.get(&comment.id).copied().unwrap_or_default(),
+                author: author.map(AuthorSummary::from),
+                comment,
+            })
+            .collect())
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct CommentSchema {
+    pub id: i32,
+    pub text: String,
+    pub post_id: i32,
+    pub user_id: i32,
+    /// the comment replied to
+    pub parent_id: Option<i32>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub author: Option<AuthorSummary>,
+    /// number of direct replies
+    pub replies: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct CommentPageSchema {
+    pub items: Vec<CommentSchema>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+}
@@ -0,0 +1,122 @@
+use super::HttpResponse;
+use crate::{
+    core::{exception::HttpException, state},
+    dtos::user_dtos::FollowUserParam,
+    extractors::Param,
+    guards::Claims,
+    http_exception, http_exception_or,
+};
+use axum::extract::State;
+use axum_macros::debug_handler;
+use entity::{
+    prelude::{User, UserFollow},
+    soft_delete::SoftDelete,
+    user_follow,
+};
+use sea_orm::{sea_query::OnConflict, ColumnTrait, EntityTrait, QueryFilter, Set};
+use std::sync::Arc;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new().routes(routes!(follow, unfollow));
+
+    OpenApiRouter::new().nest("/user/{id}/follow", router)
+}
+
+/// Follow User
+///
+/// Follow another user to get a `post:published` socket event whenever they publish a post
+/// the current user can read. Following twice changes nothing.
+#[utoipa::path(
+  put,
+  path = "",
+  params(
+    ("id" = i32, Path, description = "User database id"),
+  ),
+  responses(
+    (status = 200, description = "User followed successfully"),
+    (status = 400, description = "Users cannot follow themselves"),
+    (status = 404, description = "User not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn follow(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<FollowUserParam>,
+) -> Result<HttpResponse<()>, HttpException> {
+    if param.id == claims.user_id {
+        http_exception!(BadRequestException, "You cannot follow yourself");
+    }
+    http_exception_or!(
+        User::find_active_by_id(param.id).one(&state.db).await?,
+        NotFoundException,
+        format!("No user found with id {}", param.id)
+    );
+
+    // `insert` of the entity skips `before_save`
+    UserFollow::insert(user_follow::ActiveModel {
+        follower_id: Set(claims.user_id),
+        followed_id: Set(param.id),
+        created_at: Set(Some(chrono::Utc::now())),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            user_follow::Column::FollowerId,
+            user_follow::Column::FollowedId,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .do_nothing()
+    .exec(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("You are now following user {}", param.id)),
+        payload: None,
+    })
+}
+
+/// Unfollow User
+///
+/// Stop following a user.
+#[utoipa::path(
+  delete,
+  path = "",
+  params(
+    ("id" = i32, Path, description = "User database id"),
+  ),
+  responses(
+    (status = 200, description = "User unfollowed successfully"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn unfollow(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<FollowUserParam>,
+) -> Result<HttpResponse<()>, HttpException> {
+    UserFollow::delete_many()
+        .filter(user_follow::Column::FollowerId.eq(claims.user_id))
+        .filter(user_follow::Column::FollowedId.eq(param.id))
+        .exec(&state.db)
+        .await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("You are no longer following user {}", param.id)),
+        payload: None,
+    })
+}
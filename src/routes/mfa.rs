@@ -0,0 +1,274 @@
+use super::{HttpResponse, JsonResponse};
+use crate::{
+    core::{exception::HttpException, state},
+    dtos::user_dtos::MfaCodeDto,
+    extractors::Body,
+    guards::Claims,
+    http_exception, http_exception_or,
+    services::mfa,
+};
+use axum::extract::State;
+use axum_macros::debug_handler;
+use entity::{prelude::User, soft_delete::SoftDelete, user};
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(disable))
+        .routes(routes!(enroll))
+        .routes(routes!(verify))
+        .routes(routes!(regenerate_recovery_codes));
+
+    OpenApiRouter::new().nest("/user/mfa", router)
+}
+
+/// Start two-factor enrollment
+///
+/// Generate a new TOTP secret. Two-factor authentication is enabled once a code generated
+/// from it is sent to `/user/mfa/verify`.
+#[utoipa::path(
+  post,
+  path = "/enroll",
+  responses(
+    (status = 200, description = "Secret generated successfully", body = JsonResponse<MfaEnrollment>),
+    (status = 400, description = "Two-factor authentication already enabled"),
+    (status = 403, description = "Called with a personal access token or an impersonation session"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::MFA_TAG
+)]
+#[debug_handler]
+async fn enroll(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+) -> Result<HttpResponse<MfaEnrollment>, HttpException> {
+    let user = find_session_user(&state, &claims).await?;
+    if user.totp_enabled_at.is_some() {
+        http_exception!(
+            BadRequestException,
+            "Two-factor authentication is already enabled"
+        );
+    }
+
+    let secret = mfa::new_secret();
+    let otpauth_uri = mfa::otpauth_uri(&secret, &user.email)?;
+    let mut user = user.into_active_model();
+    user.totp_secret = Set(Some(secret.clone()));
+    user.update(&state.db).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(MfaEnrollment {
+            secret,
+            otpauth_uri,
+        }),
+    })
+}
+
+/// Confirm two-factor enrollment
+///
+/// Enable two-factor authentication with a code from the authenticator app.
+/// The recovery codes are only returned once.
+#[utoipa::path(
+  post,
+  path = "/verify",
+  request_body = MfaCodeDto,
+  responses(
+    (status = 200, description = "Two-factor authentication enabled", body = JsonResponse<Vec<String>>),
+    (status = 400, description = "Enrollment not started or already enabled"),
+    (status = 401, description = "Invalid code"),
+    (status = 403, description = "Called with a personal access token or an impersonation session"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::MFA_TAG
+)]
+#[debug_handler]
+async fn verify(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Body(input): Body<MfaCodeDto>,
+) -> Result<HttpResponse<Vec<String>>, HttpException> {
+    let user = find_session_user(&state, &claims).await?;
+    if user.totp_enabled_at.is_some() || user.totp_secret.is_none() {
+        http_exception!(
+            BadRequestException,
+            "Start the enrollment at /user/mfa/enroll first"
+        );
+    }
+    if !mfa::check_totp(&state.redis_pool, &user, input.code.trim()).await? {
+        http_exception!(
+            UnauthorizedException,
+            "Invalid two-factor authentication code"
+        );
+    }
+
+    let txn = state.db.begin().await?;
+    let user_id = user.id;
+    let mut user = user.into_active_model();
+    user.totp_enabled_at = Set(Some(chrono::Utc::now()));
+    user.update(&txn).await?;
+    let codes = mfa::replace_recovery_codes(&txn, user_id).await?;
+    txn.commit().await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(
+            "Two-factor authentication enabled, store the recovery codes in a safe place"
+                .to_string(),
+        ),
+        payload: Some(codes),
+    })
+}
+
+/// Disable two-factor authentication
+///
+/// Disable two-factor authentication with a TOTP code or a recovery code.
+#[utoipa::path(
+  delete,
+  path = "",
+  request_body = MfaCodeDto,
+  responses(
+    (status = 200, description = "Two-factor authentication disabled"),
+    (status = 400, description = "Two-factor authentication not enabled"),
+    (status = 401, description = "Invalid code"),
+    (status = 403, description = "Called with a personal access token or an impersonation session"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::MFA_TAG
+)]
+#[debug_handler]
+async fn disable(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Body(input): Body<MfaCodeDto>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let user = find_enabled_user(&state, &claims, &input.code).await?;
+
+    let txn = state.db.begin().await?;
+    let user_id = user.id;
+    let mut user = user.into_active_model();
+    user.totp_secret = Set(None);
+    user.totp_enabled_at = Set(None);
+    user.update(&txn).await?;
+    mfa::delete_recovery_codes(&txn, user_id).await?;
+    txn.commit().await?;
+
+    Ok(HttpResponse::Json {
+        message: Some("Two-factor authentication disabled".to_string()),
+        payload: None,
+    })
+}
+
+/// Regenerate recovery codes
+///
+/// Replace all recovery codes, the previous ones stop working.
+#[utoipa::path(
+  post,
+  path = "/recovery-codes",
+  request_body = MfaCodeDto,
+  responses(
+    (status = 200, description = "Recovery codes regenerated", body = JsonResponse<Vec<String>>),
+    (status = 400, description = "Two-factor authentication not enabled"),
+    (status = 401, description = "Invalid code"),
+    (status = 403, description = "Called with a personal access token or an impersonation session"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::MFA_TAG
+)]
+#[debug_handler]
+async fn regenerate_recovery_codes(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Body(input): Body<MfaCodeDto>,
+) -> Result<HttpResponse<Vec<String>>, HttpException> {
+    let user = find_enabled_user(&state, &claims, &input.code).await?;
+
+    let txn = state.db.begin().await?;
+    let codes = mfa::replace_recovery_codes(&txn, user.id).await?;
+    txn.commit().await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(codes),
+    })
+}
+
+/// Load the current user, two-factor settings can only be changed from a login session.
+async fn find_session_user(
+    state: &state::AppState,
+    claims: &Claims,
+) -> Result<user::Model, HttpException> {
+    if claims.is_api_token() {
+        http_exception!(
+            ForbiddenException,
+            "Personal access tokens cannot change two-factor authentication"
+        );
+    }
+    if claims.is_impersonated() {
+        http_exception!(
+            ForbiddenException,
+            "Impersonation sessions cannot change two-factor authentication"
+        );
+    }
+
+    let user = http_exception_or!(
+        User::find_active_by_id(claims.user_id)
+            .one(&state.db)
+            .await?,
+        NotFoundException,
+        format!("No user found with id {}", claims.user_id)
+    );
+
+    Ok(user)
+}
+
+/// Load the current user and check the second factor before a sensitive change.
+async fn find_enabled_user(
+    state: &state::AppState,
+    claims: &Claims,
+    code: &str,
+) -> Result<user::Model, HttpException> {
+    let user = find_session_user(state, claims).await?;
+    if user.totp_enabled_at.is_none() {
+        http_exception!(
+            BadRequestException,
+            "Two-factor authentication is not enabled"
+        );
+    }
+    if !mfa::verify(&state.db, &state.redis_pool, &user, code).await? {
+        http_exception!(
+            UnauthorizedException,
+            "Invalid two-factor authentication code"
+        );
+    }
+
+    Ok(user)
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct MfaEnrollment {
+    /// base32 secret for manual entry
+    pub secret: String,
+    /// `otpauth://` uri to be shown as a QR code
+    pub otpauth_uri: String,
+}
@@ -25,7 +25,7 @@
  */
 use crate::{
     core::{exception::HttpException, state},
-    guards::CookieGuard,
+    guards::{AuthGuard, VerifiedGuard},
 };
 use axum::{
     http::{StatusCode, Uri},
@@ -38,17 +38,42 @@ use std::sync::Arc;
 use utoipa::ToSchema;
 use utoipa_axum::router::OpenApiRouter;
 
+pub mod admin;
+pub mod comment;
+pub mod follow;
+pub mod mfa;
+pub mod oidc;
 pub mod post;
+pub mod privacy;
+pub mod reaction;
+pub mod tag;
+pub mod token;
 pub mod upload;
 pub mod user;
+pub mod well_known;
 
-pub fn router() -> OpenApiRouter<Arc<state::AppState>> {
+pub fn router(state: Arc<state::AppState>) -> OpenApiRouter<Arc<state::AppState>> {
     let api_v1_router = OpenApiRouter::new()
         .merge(user::protected_route())
+        .merge(follow::protected_route())
         .merge(post::protected_route())
+        .merge(comment::protected_route())
+        .merge(reaction::protected_route())
+        .merge(tag::protected_route())
+        .merge(token::protected_route())
+        .merge(mfa::protected_route())
         .merge(upload::protected_route())
-        .route_layer(middleware::from_extractor::<CookieGuard>())
-        .merge(user::public_route());
+        .merge(admin::protected_route())
+        .merge(oidc::protected_route())
+        .route_layer(middleware::from_extractor_with_state::<VerifiedGuard, _>(
+            state.clone(),
+        ))
+        .merge(user::unverified_route())
+        .merge(privacy::unverified_route())
+        .route_layer(middleware::from_extractor_with_state::<AuthGuard, _>(state))
+        .merge(user::public_route())
+        .merge(post::public_route())
+        .merge(oidc::public_route());
 
     OpenApiRouter::new().nest("/v1", api_v1_router)
 }
@@ -86,6 +111,33 @@ impl<T: Serialize> IntoResponse for HttpResponse<T> {
     }
 }
 
+/// One page of a list. `page` is set when paging by number, `next_cursor` when paging by
+/// cursor and more items follow.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>,
+    pub per_page: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Same page with its items turned into something else
+    pub fn map_items<U>(self, f: impl FnOnce(Vec<T>) -> Vec<U>) -> Page<U> {
+        Page {
+            items: f(self.items),
+            total: self.total,
+            page: self.page,
+            per_page: self.per_page,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct JsonResponse<T> {
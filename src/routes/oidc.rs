@@ -0,0 +1,389 @@
+use super::{user::start_session, HttpResponse, JsonResponse};
+use crate::{
+    core::{
+        config::{self, OidcProvider},
+        exception::HttpException,
+        state,
+    },
+    dtos::oidc_dtos::{IdentityParam, OidcCallbackQuery, OidcLoginQuery, OidcProviderParam},
+    extractors::{ClientInfo, Param, Query},
+    guards::{self, Claims},
+    http_exception, http_exception_or,
+    services::{
+        oidc::{self, PendingLogin},
+        one_time_token::{self, Purpose},
+    },
+    utils::random_token,
+};
+use axum::extract::State;
+use axum_macros::debug_handler;
+use entity::{prelude::UserIdentity, user_identity};
+use reqwest::Url;
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tower_cookies::{cookie::SameSite, Cookie, Cookies};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+// the binding cookie is only sent to the callbacks
+const BINDING_COOKIE_PATH: &str = "/api/v1/auth/oidc";
+
+pub fn public_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(login))
+        .routes(routes!(callback));
+
+    OpenApiRouter::new().nest("/auth/oidc", router)
+}
+
+pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(list_identities))
+        .routes(routes!(link_identity))
+        .routes(routes!(unlink_identity));
+
+    OpenApiRouter::new().nest("/user/me/identities", router)
+}
+
+/// Log in with an identity provider
+///
+/// Redirect to the login page of the provider. Once it calls back, the same session cookies
+/// as `/user/login` are set and the browser is sent to `redirect` on the frontend.
+#[utoipa::path(
+  get,
+  path = "/{provider}/login",
+  params(
+    ("provider" = String, Path, description = "Provider name"),
+    ("redirect" = Option<String>, Query, description = "Path of the frontend to return to"),
+  ),
+  responses(
+    (status = 307, description = "Redirect to the provider"),
+    (status = 404, description = "Unknown provider"),
+  ),
+  tag = crate::api_doc::OIDC_TAG
+)]
+#[debug_handler]
+async fn login(
+    State(state): State<Arc<state::AppState>>,
+    cookies: Cookies,
+    Param(param): Param<OidcProviderParam>,
+    Query(query): Query<OidcLoginQuery>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let provider = find_provider(&param.provider)?;
+    let binding = random_token(32);
+    let pending = PendingLogin::new(
+        &provider.name,
+        &binding,
+        safe_redirect(query.redirect),
+        None,
+    );
+    let uri = oidc::authorization_url(provider, &callback_url(provider), &pending).await?;
+    oidc::save_pending(&state.redis_pool, &pending).await?;
+    set_binding_cookie(&cookies, binding);
+
+    Ok(HttpResponse::RedirectTo { uri })
+}
+
+/// Identity provider callback
+///
+/// Called by the provider after the login, in the browser that started it. The identity is
+/// linked to the user with the same verified email, or to a new user. Users with two-factor
+/// authentication are sent to `/login/mfa` on the frontend with an `mfaToken` for
+/// `/user/login/mfa`. When linking an identity, the browser must still be logged in as the
+/// user that started it.
+#[utoipa::path(
+  get,
+  path = "/{provider}/callback",
+  params(
+    ("provider" = String, Path, description = "Provider name"),
+    ("code" = Option<String>, Query, description = "Authorization code"),
+    ("state" = Option<String>, Query, description = "State of the login redirect"),
+    ("error" = Option<String>, Query, description = "Error reported by the provider"),
+  ),
+  responses(
+    (status = 307, description = "Logged in, redirect to the frontend"),
+    (status = 400, description = "Invalid or expired state, or started in another browser"),
+    (status = 401, description = "Invalid ID token, or not logged in when linking an identity"),
+    (status = 403, description = "Account is disabled, or logged in as another user when linking an identity"),
+    (status = 409, description = "Email or identity already used by another account"),
+    (status = 502, description = "The provider could not be reached"),
+  ),
+  tag = crate::api_doc::OIDC_TAG
+)]
+#[debug_handler]
+async fn callback(
+    State(state): State<Arc<state::AppState>>,
+    cookies: Cookies,
+    client: ClientInfo,
+    Param(param): Param<OidcProviderParam>,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let config = config::Config::global();
+    let provider = find_provider(&param.provider)?;
+    if let Some(error) = query.error {
+        let login = format!("{}/login", config.app_url());
+        let uri = Url::parse_with_params(&login, &[("error", error)])
+            .map(String::from)
+            .unwrap_or(login);
+        return Ok(HttpResponse::RedirectTo { uri });
+    }
+    let (Some(code), Some(state_param)) = (query.code, query.state) else {
+        http_exception!(BadRequestException, "Missing code or state");
+    };
+
+    let pending = http_exception_or!(
+        oidc::take_pending(&state.redis_pool, &state_param)
+            .await?
+            .filter(|pending| pending.provider == provider.name),
+        BadRequestException,
+        "Invalid or expired login state"
+    );
+    let binding = cookies.get(&binding_cookie_name());
+    pending.verify_binding(binding.as_ref().map(|cookie| cookie.value()))?;
+    remove_binding_cookie(&cookies);
+    if let Some(user_id) = pending.link_user_id {
+        let token = http_exception_or!(
+            cookies.get(config.app_auth_key()),
+            UnauthorizedException,
+            "Log in again to link the identity"
+        );
+        let current = guards::authorize(&state, token.value()).await?;
+        if current.user_id != user_id {
+            http_exception!(
+                ForbiddenException,
+                "The identity link was started by another user"
+            );
+        }
+    }
+
+    let claims = oidc::exchange_code(provider, &callback_url(provider), &code, &pending).await?;
+    let redirect = format!(
+        "{}{}",
+        config.app_url(),
+        pending.redirect.as_deref().unwrap_or("/")
+    );
+
+    if let Some(user_id) = pending.link_user_id {
+        oidc::link(&state.db, &provider.name, &claims, user_id).await?;
+        return Ok(HttpResponse::RedirectTo { uri: redirect });
+    }
+
+    let user = oidc::resolve_user(&state.db, &provider.name, &claims).await?;
+    if user.disabled_at.is_some() || user.deleted_at.is_some() {
+        http_exception!(ForbiddenException, "Account is disabled");
+    }
+    if user.totp_enabled_at.is_some() {
+        let token = one_time_token::issue(&state.redis_pool, Purpose::MfaPending, user.id).await?;
+        let uri = format!("{}/login/mfa#mfaToken={token}", config.app_url());
+        return Ok(HttpResponse::RedirectTo { uri });
+    }
+    start_session(&state, &cookies, &client, user.id).await?;
+
+    Ok(HttpResponse::RedirectTo { uri: redirect })
+}
+
+/// List linked identities
+///
+/// List the identity provider accounts linked to the current user.
+#[utoipa::path(
+  get,
+  path = "",
+  responses(
+    (status = 200, description = "List identities successfully", body = JsonResponse<Vec<IdentitySchema>>),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::OIDC_TAG
+)]
+#[debug_handler]
+async fn list_identities(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+) -> Result<HttpResponse<Vec<user_identity::Model>>, HttpException> {
+    let identities = UserIdentity::find()
+        .filter(user_identity::Column::UserId.eq(claims.user_id))
+        .order_by_asc(user_identity::Column::Id)
+        .all(&state.db)
+        .await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(identities),
+    })
+}
+
+/// Link an identity
+///
+/// Get the url of the provider's login page. The account used there is linked to the
+/// current user when the provider calls back.
+#[utoipa::path(
+  post,
+  path = "/link/{provider}",
+  params(
+    ("provider" = String, Path, description = "Provider name"),
+    ("redirect" = Option<String>, Query, description = "Path of the frontend to return to"),
+  ),
+  responses(
+    (status = 200, description = "Url of the provider's login page", body = JsonResponse<String>),
+    (status = 403, description = "Called with a personal access token or an impersonation session"),
+    (status = 404, description = "Unknown provider"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::OIDC_TAG
+)]
+#[debug_handler]
+async fn link_identity(
+    State(state): State<Arc<state::AppState>>,
+    cookies: Cookies,
+    claims: Claims,
+    Param(param): Param<OidcProviderParam>,
+    Query(query): Query<OidcLoginQuery>,
+) -> Result<HttpResponse<String>, HttpException> {
+    if claims.is_api_token() {
+        http_exception!(
+            ForbiddenException,
+            "Personal access tokens cannot link identities"
+        );
+    }
+    if claims.is_impersonated() {
+        http_exception!(
+            ForbiddenException,
+            "Impersonation sessions cannot link identities"
+        );
+    }
+
+    let provider = find_provider(&param.provider)?;
+    let binding = random_token(32);
+    let pending = PendingLogin::new(
+        &provider.name,
+        &binding,
+        safe_redirect(query.redirect),
+        Some(claims.user_id),
+    );
+    let url = oidc::authorization_url(provider, &callback_url(provider), &pending).await?;
+    oidc::save_pending(&state.redis_pool, &pending).await?;
+    set_binding_cookie(&cookies, binding);
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(url),
+    })
+}
+
+/// Unlink an identity
+///
+/// Stop logging in with an identity provider account.
+#[utoipa::path(
+  delete,
+  path = "/{id}",
+  params(
+    ("id" = i32, Path, description = "Identity database id"),
+  ),
+  responses(
+    (status = 200, description = "Identity unlinked"),
+    (status = 403, description = "Called with an impersonation session"),
+    (status = 404, description = "Identity not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::OIDC_TAG
+)]
+#[debug_handler]
+async fn unlink_identity(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<IdentityParam>,
+) -> Result<HttpResponse<()>, HttpException> {
+    if claims.is_impersonated() {
+        http_exception!(
+            ForbiddenException,
+            "Impersonation sessions cannot unlink identities"
+        );
+    }
+
+    let identity = http_exception_or!(
+        UserIdentity::find_by_id(param.id)
+            .filter(user_identity::Column::UserId.eq(claims.user_id))
+            .one(&state.db)
+            .await?,
+        NotFoundException,
+        format!("No identity found with id {}", param.id)
+    );
+    identity.delete(&state.db).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("The identity {} has been unlinked", param.id)),
+        payload: None,
+    })
+}
+
+fn find_provider(name: &str) -> Result<&'static OidcProvider, HttpException> {
+    let provider = http_exception_or!(
+        config::Config::global().oidc_provider(name),
+        NotFoundException,
+        format!("Unknown identity provider {}", name)
+    );
+
+    Ok(provider)
+}
+
+fn callback_url(provider: &OidcProvider) -> String {
+    format!(
+        "{}/api/v1/auth/oidc/{}/callback",
+        config::Config::global().api_url(),
+        provider.name
+    )
+}
+
+fn binding_cookie_name() -> String {
+    format!("{}_oidc", config::Config::global().app_auth_key())
+}
+
+/// Lax so the cookie comes along when the provider redirects back to the callback
+fn set_binding_cookie(cookies: &Cookies, binding: String) {
+    let cookie = Cookie::build((binding_cookie_name(), binding))
+        .path(BINDING_COOKIE_PATH)
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::seconds(oidc::PENDING_LOGIN_TTL as i64))
+        .secure(true)
+        .http_only(true)
+        .build();
+    cookies.add(cookie);
+}
+
+fn remove_binding_cookie(cookies: &Cookies) {
+    cookies.remove(
+        Cookie::build(binding_cookie_name())
+            .path(BINDING_COOKIE_PATH)
+            .build(),
+    );
+}
+
+/// Only paths of the frontend are accepted so the login cannot redirect to another site
+fn safe_redirect(redirect: Option<String>) -> Option<String> {
+    redirect.filter(|path| path.starts_with('/') && !path.starts_with("//") && !path.contains('\\'))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct IdentitySchema {
+    pub id: i32,
+    pub user_id: i32,
+    #[schema(example = "google")]
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
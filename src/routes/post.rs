@@ -1,37 +1,197 @@
-use super::{HttpResponse, JsonResponse};
+use super::{HttpResponse, JsonResponse, Page};
 use crate::{
-    core::{exception::HttpException, state},
+    core::{
+        exception::HttpException,
+        state::{self, RedisPool},
+    },
+    dtos::post_dtos::{
+        ListPostsDto, PatchPostDto, PostDto, PostSort, QueryPostDto, SearchPostsDto,
+    },
+    extractors::{encode_cursor, Body, Pagination, Param, Query, SortOrder},
     guards::Claims,
-    http_exception_or,
+    http_exception, http_exception_or,
+    services::{
+        publisher,
+        reaction::{self, ReactionCounts},
+        tag,
+    },
 };
-use axum::extract::{Path, State};
+use axum::extract::State;
 use axum_macros::debug_handler;
-use entity::{post, prelude::Post};
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use entity::{
+    post,
+    prelude::{Post, User},
+    sea_orm_active_enums::{PostStatus, Visibility},
+    soft_delete::SoftDelete,
+    user,
+};
+use sea_orm::{
+    sea_query::{extension::postgres::PgExpr, Expr, Query as SelectQuery, SelectStatement},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, FromQueryResult, IntoActiveModel,
+    LoaderTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select, Set,
+    TransactionTrait,
+};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use utoipa::ToSchema;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
+// marks the matches in the search highlights, replaced by `<mark>` tags once the text is escaped
+const MATCH_START: char = '\u{2}';
+const MATCH_STOP: char = '\u{3}';
+const HIGHLIGHT: &str = "StartSel=\"\u{2}\", StopSel=\"\u{3}\"";
+
 pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
     let router = OpenApiRouter::new()
-        .routes(routes!(get_one))
-        .routes(routes!(get_all));
+        .routes(routes!(get_one, update_one, patch_one, delete_one))
+        .routes(routes!(get_all, create_one))
+        .routes(routes!(search))
+        .routes(routes!(restore_one));
 
     OpenApiRouter::new().nest("/post", router)
 }
 
+/// Readable without logging in
+pub fn public_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(feed))
+        .routes(routes!(get_shared));
+
+    OpenApiRouter::new().nest("/posts", router)
+}
+
+/// Public feed
+///
+/// List the published public posts of every user with a summary of their author, the most
+/// recently published first.
+/// The feed is paged by cursor: leave it out for the first page, then pass the `nextCursor`
+/// of the previous page.
+#[utoipa::path(
+  get,
+  path = "/feed",
+  params(
+    ("per_page" = Option<u64>, Query, description = "Page size, 20 by default, at most 100"),
+    ("cursor" = Option<String>, Query, description = "Cursor of the page"),
+  ),
+  responses(
+    (status = 200, description = "List the feed successfully", body = JsonResponse<FeedPageSchema>),
+    (status = 400, description = "Invalid cursor"),
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn feed(
+    State(state): State<Arc<state::AppState>>,
+    pagination: Pagination,
+) -> Result<HttpResponse<Page<FeedPost>>, HttpException> {
+    let pagination = match pagination {
+        Pagination::Offset { page: 1, per_page } => Pagination::Cursor {
+            cursor: None,
+            per_page,
+        },
+        Pagination::Offset { .. } => {
+            http_exception!(BadRequestException, "The feed is paged by cursor")
+        }
+        pagination => pagination,
+    };
+
+    let query = Post::find_active()
+        .filter(post::Column::Visibility.eq(Visibility::Public))
+        .filter(post::Column::Status.eq(PostStatus::Published))
+        .filter(post::Column::UserId.in_subquery(readable_authors()));
+    let page = paginate(
+        &state.db,
+        query,
+        &pagination,
+        PostSort::PublishedAt,
+        SortOrder::Desc,
+    )
+    .await?;
+    let authors = page.items.load_one(User, &state.db).await?;
+    let mut details = PostDetails::load(&state.db, &state.redis_pool, &page.items).await?;
+    let page = page.map_items(|posts| {
+        posts
+            .into_iter()
+            .zip(authors)
+            .map(|(post, author)| FeedPost::new(details.view(post), author))
+            .collect()
+    });
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(page),
+    })
+}
+
+/// Query a shared Post
+///
+/// Read a published public or unlisted Post with a summary of its author, without logging in.
+#[utoipa::path(
+  get,
+  path = "/{id}",
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+  ),
+  responses(
+    (status = 200, description = "Query Post details successfully", body = JsonResponse<FeedPostSchema>),
+    (status = 404, description = "Post not found"),
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn get_shared(
+    State(state): State<Arc<state::AppState>>,
+    Param(param): Param<QueryPostDto>,
+) -> Result<HttpResponse<FeedPost>, HttpException> {
+    let post = http_exception_or!(
+        Post::find_active_by_id(param.id)
+            .filter(post::Column::Visibility.ne(Visibility::Private))
+            .filter(post::Column::Status.eq(PostStatus::Published))
+            .filter(post::Column::UserId.in_subquery(readable_authors()))
+            .one(&state.db)
+            .await?,
+        NotFoundException,
+        format!("No post found with id {}", param.id)
+    );
+    let author = post.find_related(User).one(&state.db).await?;
+    let post = PostView::load(&state.db, &state.redis_pool, post).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(FeedPost::new(post, author)),
+    })
+}
+
 /// List all Post items
 ///
-/// List all Post items from database storage.
+/// List the Post items of the current user page by page, newest first by default.
+/// Pass `page` to page by number, or an empty `cursor` and then the `nextCursor` of the
+/// previous page to page by cursor, which is stable while posts are being added.
 #[utoipa::path(
   get,
 	path = "",
+	params(
+    ("page" = Option<u64>, Query, description = "Page number starting at 1, at most 10000"),
+    ("per_page" = Option<u64>, Query, description = "Page size, 20 by default, at most 100"),
+    ("cursor" = Option<String>, Query, description = "Cursor of the page, empty for the first one"),
+    ("category" = Option<String>, Query, description = "Only posts of this category"),
+    ("visibility" = Option<String>, Query, description = "Only posts with this visibility"),
+    ("status" = Option<String>, Query, description = "Only posts with this status"),
+    ("from" = Option<String>, Query, description = "Only posts created at or after, RFC 3339"),
+    ("to" = Option<String>, Query, description = "Only posts created before, RFC 3339"),
+    ("title" = Option<String>, Query, description = "Part of the title"),
+    ("tags" = Option<String>, Query, description = "Comma separated, posts carrying all of them"),
+    ("sort" = Option<PostSort>, Query, description = "created_at by default, published_at leaves out posts never published"),
+    ("order" = Option<String>, Query, description = "asc or desc, desc by default"),
+  ),
 	responses(
-		(status = 200, description = "List all posts successfully", body = JsonResponse<Vec<PostSchema>>)
+		(status = 200, description = "List all posts successfully", body = JsonResponse<PostPageSchema>),
+		(status = 400, description = "Invalid filter or cursor"),
 	),
 	security(
-    ("cookie_security" = [])
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
   ),
 	tag = crate::api_doc::POST_TAG
 )]
@@ -39,15 +199,164 @@ pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
 async fn get_all(
     State(state): State<Arc<state::AppState>>,
     claims: Claims,
-) -> Result<HttpResponse<Vec<post::Model>>, HttpException> {
-    let posts = Post::find()
-        .filter(post::Column::UserId.eq(claims.user_id))
+    pagination: Pagination,
+    Query(input): Query<ListPostsDto>,
+) -> Result<HttpResponse<Page<PostView>>, HttpException> {
+    let mut query = Post::find_active().filter(post::Column::UserId.eq(claims.user_id));
+    if let Some(category) = input.category {
+        query = query.filter(post::Column::Category.eq(category));
+    }
+    if let Some(visibility) = input.visibility {
+        query = query.filter(post::Column::Visibility.eq(visibility));
+    }
+    if let Some(status) = input.status {
+        query = query.filter(post::Column::Status.eq(status));
+    }
+    if let Some(from) = input.from {
+        query = query.filter(post::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = input.to {
+        query = query.filter(post::Column::CreatedAt.lt(to));
+    }
+    if let Some(title) = input
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+    {
+        query = query
+            .filter(Expr::col((post::Entity, post::Column::Title)).ilike(format!("%{title}%")));
+    }
+    let names: Vec<&str> = input
+        .tags
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .collect();
+    for name in tag::normalize(&names) {
+        query = query.filter(post::Column::Id.in_subquery(tag::tagged(&name)));
+    }
+
+    let page = paginate(
+        &state.db,
+        query,
+        &pagination,
+        input.sort.unwrap_or_default(),
+        input.order.unwrap_or_default(),
+    )
+    .await?;
+    let mut details = PostDetails::load(&state.db, &state.redis_pool, &page.items).await?;
+    let page = page.map_items(|posts| posts.into_iter().map(|post| details.view(post)).collect());
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(page),
+    })
+}
+
+/// Search Post items
+///
+/// Search the posts of the current user and the published public posts of others, best
+/// matches first. `titleHighlight` and `textHighlight` are HTML escaped, with the matches
+/// wrapped in `<mark>` tags. Results are paged by number.
+#[utoipa::path(
+  get,
+  path = "/search",
+  params(
+    ("q" = String, Query, description = "Search terms"),
+    ("mine" = Option<bool>, Query, description = "Only search the posts of the current user"),
+    ("page" = Option<u64>, Query, description = "Page number starting at 1, at most 10000"),
+    ("per_page" = Option<u64>, Query, description = "Page size, 20 by default, at most 100"),
+  ),
+  responses(
+    (status = 200, description = "Search posts successfully", body = JsonResponse<SearchPageSchema>),
+    (status = 400, description = "Invalid search"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn search(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    pagination: Pagination,
+    Query(input): Query<SearchPostsDto>,
+) -> Result<HttpResponse<Page<SearchHit>>, HttpException> {
+    let Pagination::Offset { page, per_page } = pagination else {
+        http_exception!(BadRequestException, "Search results are paged by number");
+    };
+    let offset = http_exception_or!(
+        (page - 1).checked_mul(per_page),
+        BadRequestException,
+        "Invalid page"
+    );
+    let terms = input.q.trim().to_string();
+
+    let query = Post::find_active()
+        .filter(if input.mine.unwrap_or(false) {
+            Condition::all().add(post::Column::UserId.eq(claims.user_id))
+        } else {
+            visible_to(claims.user_id)
+        })
+        .filter(Expr::cust_with_values(
+            "search_vector @@ websearch_to_tsquery('english', $1)",
+            [terms.clone()],
+        ));
+    let total = query.clone().count(&state.db).await?;
+
+    let items = query
+        .column_as(
+            Expr::cust_with_values(
+                "ts_rank(search_vector, websearch_to_tsquery('english', $1))",
+                [terms.clone()],
+            ),
+            "rank",
+        )
+        .column_as(
+            Expr::cust_with_values(
+                "ts_headline('english', title, websearch_to_tsquery('english', $1), $2)",
+                [terms.clone(), format!("{HIGHLIGHT}, HighlightAll=true")],
+            ),
+            "title_highlight",
+        )
+        .column_as(
+            Expr::cust_with_values(
+                "ts_headline('english', text, websearch_to_tsquery('english', $1), $2)",
+                [
+                    terms,
+                    format!("{HIGHLIGHT}, MaxFragments=2, MaxWords=30, MinWords=10"),
+                ],
+            ),
+            "text_highlight",
+        )
+        .order_by_desc(Expr::col("rank"))
+        .order_by_desc(post::Column::Id)
+        .offset(offset)
+        .limit(per_page)
+        .into_model::<SearchHit>()
         .all(&state.db)
-        .await?;
+        .await?
+        .into_iter()
+        .map(|hit| SearchHit {
+            title_highlight: mark_matches(&hit.title_highlight),
+            text_highlight: mark_matches(&hit.text_highlight),
+            ..hit
+        })
+        .collect();
 
     Ok(HttpResponse::Json {
         message: None,
-        payload: Some(posts),
+        payload: Some(Page {
+            items,
+            total,
+            page: Some(page),
+            per_page,
+            next_cursor: None,
+        }),
     })
 }
 
@@ -59,33 +368,512 @@ async fn get_all(
   path = "/{id}",
   responses(
     (status = 200, description = "Query Post details successfully", body = JsonResponse<PostSchema>),
+    (status = 403, description = "Post belongs to another user"),
 		(status = 404, description = "Post not found")
   ),
   params(
     ("id" = i32, Path, description = "Post database id"),
   ),
   security(
-    ("cookie_security" = [])
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
   ),
 	tag = crate::api_doc::POST_TAG
 )]
 #[debug_handler]
 async fn get_one(
     State(state): State<Arc<state::AppState>>,
-    Path(id): Path<i32>,
-) -> Result<HttpResponse<post::Model>, HttpException> {
+    claims: Claims,
+    Param(input): Param<QueryPostDto>,
+) -> Result<HttpResponse<PostView>, HttpException> {
+    let post = find_owned_post(&state.db, input.id, &claims).await?;
+    let post = PostView::load(&state.db, &state.redis_pool, post).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(post),
+    })
+}
+
+/// Create new Post
+///
+/// Create a new Post owned by the current user. It is published right away unless it is a
+/// `draft`, or `scheduled` for a future `published_at`. Followers of the user get a
+/// `post:published` socket event once a public post goes out.
+#[utoipa::path(
+  post,
+  path = "",
+  request_body = PostDto,
+  responses(
+    (status = 200, description = "Post created successfully", body = JsonResponse<PostSchema>),
+    (status = 400, description = "Post with the same title already exists"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn create_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Body(input): Body<PostDto>,
+) -> Result<HttpResponse<PostView>, HttpException> {
+    let status = input.status.unwrap_or_default();
+    let published_at =
+        publisher::published_at(status, input.published_at, None, chrono::Utc::now())?;
+
+    let txn = state.db.begin().await?;
+    let post = post::ActiveModel {
+        title: Set(input.title),
+        text: Set(input.text),
+        category: Set(input.category),
+        visibility: Set(input.visibility.unwrap_or_default()),
+        status: Set(status),
+        published_at: Set(published_at),
+        user_id: Set(claims.user_id),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    let tags = tag::set_post_tags(&txn, post.id, &input.tags.unwrap_or_default()).await?;
+    txn.commit().await?;
+    publisher::notify_published(&state, &post).await;
+    let post = PostView {
+        post,
+        tags,
+        reactions: ReactionCounts::new(),
+    };
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(post),
+    })
+}
+
+/// Replace Post by id
+///
+/// Replace all editable fields of a Post owned by the current user. Its visibility and tags
+/// are kept unless given, its status is only changed when `status` or `publishedAt` is given.
+#[utoipa::path(
+  put,
+  path = "/{id}",
+  request_body = PostDto,
+  responses(
+    (status = 200, description = "Post updated successfully", body = JsonResponse<PostSchema>),
+    (status = 403, description = "Post belongs to another user"),
+    (status = 404, description = "Post not found")
+  ),
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn update_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryPostDto>,
+    Body(input): Body<PostDto>,
+) -> Result<HttpResponse<PostView>, HttpException> {
+    let txn = state.db.begin().await?;
+    let post = find_owned_post(&txn, param.id, &claims).await?;
+    let was_published = post.status == PostStatus::Published;
+    // without a status or a publication time the post stays a draft, scheduled or published
+    let status = input.status.unwrap_or(post.status);
+    let published_at = if input.status.is_some() || input.published_at.is_some() {
+        publisher::published_at(
+            status,
+            input.published_at,
+            post.published_at,
+            chrono::Utc::now(),
+        )?
+    } else {
+        post.published_at
+    };
+
+    let mut post = post.into_active_model();
+    post.title = Set(input.title);
+    post.text = Set(input.text);
+    post.category = Set(input.category);
+    if let Some(visibility) = input.visibility {
+        post.visibility = Set(visibility);
+    }
+    post.status = Set(status);
+    post.published_at = Set(published_at);
+    let post = post.update(&txn).await?;
+    if let Some(tags) = input.tags {
+        tag::set_post_tags(&txn, post.id, &tags).await?;
+    }
+    txn.commit().await?;
+    if !was_published {
+        publisher::notify_published(&state, &post).await;
+    }
+    let post = PostView::load(&state.db, &state.redis_pool, post).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(post),
+    })
+}
+
+/// Update Post by id
+///
+/// Update only the given fields of a Post owned by the current user.
+#[utoipa::path(
+  patch,
+  path = "/{id}",
+  request_body = PatchPostDto,
+  responses(
+    (status = 200, description = "Post updated successfully", body = JsonResponse<PostSchema>),
+    (status = 403, description = "Post belongs to another user"),
+    (status = 404, description = "Post not found")
+  ),
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn patch_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryPostDto>,
+    Body(input): Body<PatchPostDto>,
+) -> Result<HttpResponse<PostView>, HttpException> {
+    let txn = state.db.begin().await?;
+    let post = find_owned_post(&txn, param.id, &claims).await?;
+    let was_published = post.status == PostStatus::Published;
+    // the publication only changes when asked to, a due scheduled post stays as it is
+    let publication = if input.status.is_some() || input.published_at.is_some() {
+        let status = input.status.unwrap_or(post.status);
+        let published_at = publisher::published_at(
+            status,
+            input.published_at,
+            post.published_at,
+            chrono::Utc::now(),
+        )?;
+        Some((status, published_at))
+    } else {
+        None
+    };
+
+    let mut post = post.into_active_model();
+    if let Some(title) = input.title {
+        post.title = Set(title);
+    }
+    if let Some(text) = input.text {
+        post.text = Set(text);
+    }
+    if let Some(category) = input.category {
+        post.category = Set(Some(category));
+    }
+    if let Some(visibility) = input.visibility {
+        post.visibility = Set(visibility);
+    }
+    if let Some((status, published_at)) = publication {
+        post.status = Set(status);
+        post.published_at = Set(published_at);
+    }
+    let post = post.update(&txn).await?;
+    if let Some(tags) = input.tags {
+        tag::set_post_tags(&txn, post.id, &tags).await?;
+    }
+    txn.commit().await?;
+    if !was_published {
+        publisher::notify_published(&state, &post).await;
+    }
+    let post = PostView::load(&state.db, &state.redis_pool, post).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(post),
+    })
+}
+
+/// Delete Post by id
+///
+/// Soft delete a Post owned by the current user, it can be restored until it is purged.
+#[utoipa::path(
+  delete,
+  path = "/{id}",
+  responses(
+    (status = 200, description = "Post delete done successfully"),
+    (status = 403, description = "Post belongs to another user"),
+    (status = 404, description = "Post not found")
+  ),
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn delete_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryPostDto>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let mut post = find_owned_post(&state.db, param.id, &claims)
+        .await?
+        .into_active_model();
+    post.deleted_at = Set(Some(chrono::Utc::now()));
+    post.update(&state.db).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!(
+            "The post {} has been successfully deleted",
+            param.id
+        )),
+        payload: None,
+    })
+}
+
+/// Restore Post by id
+///
+/// Bring back a soft-deleted Post owned by the current user.
+#[utoipa::path(
+  post,
+  path = "/{id}/restore",
+  responses(
+    (status = 200, description = "Post restored successfully", body = JsonResponse<PostSchema>),
+    (status = 403, description = "Post belongs to another user"),
+    (status = 404, description = "No deleted post found")
+  ),
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn restore_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryPostDto>,
+) -> Result<HttpResponse<PostView>, HttpException> {
     let post = http_exception_or!(
-        Post::find_by_id(id).one(&state.db).await?,
+        Post::find_deleted()
+            .filter(post::Column::Id.eq(param.id))
+            .one(&state.db)
+            .await?,
         NotFoundException,
-        format!("No post found with id {}", id)
+        format!("No deleted post found with id {}", param.id)
     );
+    check_owner(&post, &claims)?;
+
+    let mut post = post.into_active_model();
+    post.deleted_at = Set(None);
+    let post = post.update(&state.db).await?;
+    let post = PostView::load(&state.db, &state.redis_pool, post).await?;
 
     Ok(HttpResponse::Json {
-        message: None,
+        message: Some(format!(
+            "The post {} has been successfully restored",
+            param.id
+        )),
         payload: Some(post),
     })
 }
 
+/// Posts of the user and published public posts of others, as in the feed
+pub(crate) fn visible_to(user_id: i32) -> Condition {
+    Condition::any().add(post::Column::UserId.eq(user_id)).add(
+        Condition::all()
+            .add(post::Column::Visibility.eq(Visibility::Public))
+            .add(post::Column::Status.eq(PostStatus::Published))
+            .add(post::Column::UserId.in_subquery(readable_authors())),
+    )
+}
+
+/// Users whose shared posts can be read by others
+fn readable_authors() -> SelectStatement {
+    SelectQuery::select()
+        .column((user::Entity, user::Column::Id))
+        .from(user::Entity)
+        .and_where(Expr::col((user::Entity, user::Column::DeletedAt)).is_null())
+        .and_where(Expr::col((user::Entity, user::Column::DisabledAt)).is_null())
+        .to_owned()
+}
+
+/// Where a page of posts ends, the next page starts after it
+#[derive(Deserialize, Serialize)]
+struct PostCursor {
+    sort: PostSort,
+    /// microseconds since the epoch, the formatted timestamps are truncated to seconds
+    at: i64,
+    id: i32,
+}
+
+impl PostCursor {
+    fn new(post: &post::Model, sort: PostSort) -> Option<Self> {
+        let at = match sort {
+            PostSort::CreatedAt => post.created_at,
+            PostSort::UpdatedAt => post.updated_at,
+            PostSort::PublishedAt => post.published_at,
+        }?;
+
+        Some(Self {
+            sort,
+            at: at.timestamp_micros(),
+            id: post.id,
+        })
+    }
+}
+
+/// Sort a listing and fetch the page asked for, the id breaks ties between equal timestamps
+async fn paginate<C>(
+    db: &C,
+    query: Select<post::Entity>,
+    pagination: &Pagination,
+    sort: PostSort,
+    order: SortOrder,
+) -> Result<Page<post::Model>, HttpException>
+where
+    C: ConnectionTrait,
+{
+    let column = match sort {
+        PostSort::CreatedAt => post::Column::CreatedAt,
+        PostSort::UpdatedAt => post::Column::UpdatedAt,
+        PostSort::PublishedAt => post::Column::PublishedAt,
+    };
+    // posts that were never published have nothing to sort by
+    let query = match sort {
+        PostSort::PublishedAt => query.filter(column.is_not_null()),
+        _ => query,
+    };
+    let total = query.clone().count(db).await?;
+    let query = query
+        .order_by(column, order.into())
+        .order_by(post::Column::Id, order.into());
+    let per_page = pagination.per_page();
+
+    if let Pagination::Offset { page, .. } = pagination {
+        let items = query.paginate(db, per_page).fetch_page(page - 1).await?;
+        return Ok(Page {
+            items,
+            total,
+            page: Some(*page),
+            per_page,
+            next_cursor: None,
+        });
+    }
+
+    let mut query = query;
+    if let Some(position) = pagination.position::<PostCursor>()? {
+        let at = chrono::DateTime::from_timestamp_micros(position.at)
+            .filter(|_| position.sort == sort)
+            .ok_or(HttpException::BadRequestException(Some(
+                "Invalid cursor".to_string(),
+            )))?;
+        query = query.filter(match order {
+            SortOrder::Asc => Condition::any()
+                .add(column.gt(at))
+                .add(column.eq(at).and(post::Column::Id.gt(position.id))),
+            SortOrder::Desc => Condition::any()
+                .add(column.lt(at))
+                .add(column.eq(at).and(post::Column::Id.lt(position.id))),
+        });
+    }
+
+    // one more than the page tells whether another page follows
+    let mut items = query.limit(per_page + 1).all(db).await?;
+    let next_cursor = if items.len() as u64 > per_page {
+        items.truncate(per_page as usize);
+        items
+            .last()
+            .and_then(|post| PostCursor::new(post, sort))
+            .map(|cursor| encode_cursor(&cursor))
+    } else {
+        None
+    };
+
+    Ok(Page {
+        items,
+        total,
+        page: None,
+        per_page,
+        next_cursor,
+    })
+}
+
+/// Load a post by id and make sure it belongs to the current user.
+async fn find_owned_post<C>(db: &C, id: i32, claims: &Claims) -> Result<post::Model, HttpException>
+where
+    C: ConnectionTrait,
+{
+    let post = http_exception_or!(
+        Post::find_active_by_id(id).one(db).await?,
+        NotFoundException,
+        format!("No post found with id {}", id)
+    );
+    check_owner(&post, claims)?;
+
+    Ok(post)
+}
+
+/// Load a post the current user can read, their own or a shared published one.
+pub(crate) async fn find_readable_post<C>(
+    db: &C,
+    id: i32,
+    claims: &Claims,
+) -> Result<post::Model, HttpException>
+where
+    C: ConnectionTrait,
+{
+    let post = http_exception_or!(
+        Post::find_active_by_id(id)
+            .filter(
+                Condition::any()
+                    .add(post::Column::UserId.eq(claims.user_id))
+                    .add(
+                        Condition::all()
+                            .add(post::Column::Visibility.ne(Visibility::Private))
+                            .add(post::Column::Status.eq(PostStatus::Published))
+                            .add(post::Column::UserId.in_subquery(readable_authors())),
+                    ),
+            )
+            .one(db)
+            .await?,
+        NotFoundException,
+        format!("No post found with id {}", id)
+    );
+
+    Ok(post)
+}
+
+fn check_owner(post: &post::Model, claims: &Claims) -> Result<(), HttpException> {
+    if post.user_id != claims.user_id {
+        http_exception!(
+            ForbiddenException,
+            format!("You are not allowed to access post {}", post.id)
+        );
+    }
+
+    Ok(())
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct PostSchema {
@@ -95,6 +883,217 @@ struct PostSchema {
     pub text: String,
     #[schema(default = "Feed")]
     pub category: String,
+    #[schema(example = "private")]
+    pub visibility: String,
+    #[schema(example = "published")]
+    pub status: String,
+    pub published_at: Option<String>,
+    #[schema(example = json!(["rust", "web"]))]
+    pub tags: Vec<String>,
+    /// users who reacted with each reaction
+    #[schema(example = json!({"like": 3, "wow": 1}))]
+    pub reactions: HashMap<String, i64>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub deleted_at: Option<String>,
+}
+
+/// A search result with the matches highlighted
+#[derive(Serialize, FromQueryResult)]
+#[serde(rename_all = "camelCase")]
+struct SearchHit {
+    #[serde(flatten)]
+    #[sea_orm(nested)]
+    post: post::Model,
+    title_highlight: String,
+    text_highlight: String,
+    rank: f32,
+}
+
+/// Escape a highlight from `ts_headline` and turn its match markers into `<mark>` tags, the
+/// posts of other users must not be able to slip markup into the results
+fn mark_matches(highlight: &str) -> String {
+    let mut marked = String::with_capacity(highlight.len());
+    for c in highlight.chars() {
+        match c {
+            MATCH_START => marked.push_str("<mark>"),
+            MATCH_STOP => marked.push_str("</mark>"),
+            '&' => marked.push_str("&amp;"),
+            '<' => marked.push_str("&lt;"),
+            '>' => marked.push_str("&gt;"),
+            '"' => marked.push_str("&quot;"),
+            '\'' => marked.push_str("&#39;"),
+            c => marked.push(c),
+        }
+    }
+
+    marked
+}
+
+/// A post with the names of its tags and the counts of its reactions
+#[derive(Serialize)]
+struct PostView {
+    #[serde(flatten)]
+    post: post::Model,
+    tags: Vec<String>,
+    reactions: ReactionCounts,
+}
+
+impl PostView {
+    async fn load<C>(db: &C, pool: &RedisPool, post: post::Model) -> Result<Self, HttpException>
+    where
+        C: ConnectionTrait,
+    {
+        let mut details = PostDetails::load(db, pool, std::slice::from_ref(&post)).await?;
+
+        Ok(details.view(post))
+    }
+}
+
+/// Tags and reaction counts of a list of posts, loaded at once
+struct PostDetails {
+    tags: HashMap<i32, Vec<String>>,
+    reactions: HashMap<i32, ReactionCounts>,
+}
+
+impl PostDetails {
+    async fn load<C>(db: &C, pool: &RedisPool, posts: &[post::Model]) -> Result<Self, HttpException>
+    where
+        C: ConnectionTrait,
+    {
+        let ids: Vec<i32> = posts.iter().map(|post| post.id).collect();
+
+        Ok(Self {
+            tags: tag::names_by_post(db, &ids).await?,
+            reactions: reaction::counts(db, pool, &ids).await?,
+        })
+    }
+
+    fn view(&mut self, post: post::Model) -> PostView {
+        PostView {
+            tags: self.tags.remove(&post.id).unwrap_or_default(),
+            reactions: self.reactions.remove(&post.id).unwrap_or_default(),
+            post,
+        }
+    }
+}
+
+/// A shared post with who wrote it
+#[derive(Serialize)]
+struct FeedPost {
+    #[serde(flatten)]
+    post: PostView,
+    author: Option<AuthorSummary>,
+}
+
+impl FeedPost {
+    fn new(post: PostView, author: Option<user::Model>) -> Self {
+        Self {
+            post,
+            author: author.map(AuthorSummary::from),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AuthorSummary {
+    pub id: i32,
+    pub name: String,
+}
+
+impl From<user::Model> for AuthorSummary {
+    fn from(user: user::Model) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct FeedPostSchema {
+    pub id: i32,
+    pub user_id: i32,
+    pub title: String,
+    pub text: String,
+    #[schema(default = "Feed")]
+    pub category: String,
+    #[schema(example = "public")]
+    pub visibility: String,
+    pub status: String,
+    pub published_at: String,
+    pub tags: Vec<String>,
+    pub reactions: HashMap<String, i64>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub author: Option<AuthorSummary>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct FeedPageSchema {
+    pub items: Vec<FeedPostSchema>,
+    pub total: u64,
+    pub per_page: u64,
+    /// set when more posts follow
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct SearchHitSchema {
+    pub id: i32,
+    pub user_id: i32,
+    pub title: String,
+    pub text: String,
+    #[schema(default = "Feed")]
+    pub category: String,
+    #[schema(example = "public")]
+    pub visibility: String,
+    pub status: String,
+    pub published_at: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    #[schema(example = "Full-text <mark>search</mark>")]
+    pub title_highlight: String,
+    pub text_highlight: String,
+    pub rank: f32,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct SearchPageSchema {
+    pub items: Vec<SearchHitSchema>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct PostPageSchema {
+    pub items: Vec<PostSchema>,
+    pub total: u64,
+    /// set when paging by number
+    pub page: Option<u64>,
+    pub per_page: u64,
+    /// set when paging by cursor and more posts follow
+    pub next_cursor: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_matches_escapes_post_text() {
+        let highlight =
+            format!("<script>alert('x')</script> {MATCH_START}rust{MATCH_STOP} & \"friends\"");
+        assert_eq!(
+            mark_matches(&highlight),
+            "&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt; <mark>rust</mark> &amp; &quot;friends&quot;"
+        );
+    }
 }
@@ -0,0 +1,183 @@
+use super::{
+    user::{find_current_user, remove_auth_cookies},
+    HttpResponse, JsonResponse,
+};
+use crate::{
+    core::{exception::HttpException, state},
+    dtos::user_dtos::{EraseAccountDto, ExportParam},
+    extractors::{Body, Param},
+    guards::Claims,
+    http_exception, http_exception_or,
+    services::{mfa, personal_data},
+};
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use axum_macros::debug_handler;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio_util::io::ReaderStream;
+use tower_cookies::Cookies;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+/// Stays available to users who have not verified their email yet
+pub fn unverified_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(start_export))
+        .routes(routes!(download_export))
+        .routes(routes!(erase));
+
+    OpenApiRouter::new().nest("/user/me", router)
+}
+
+/// Export personal data
+///
+/// Start building a zip archive of the profile, posts, comments, reactions, followed users,
+/// uploads, sessions, access tokens and linked identities of the current user. An
+/// `export:ready` socket event with the id is sent once it can be downloaded from
+/// `/user/me/export/{id}`, `export:failed` if it could not be built.
+#[utoipa::path(
+  get,
+  path = "/export",
+  responses(
+    (status = 200, description = "Export started", body = JsonResponse<ExportSchema>),
+    (status = 429, description = "An export is already being built"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn start_export(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+) -> Result<HttpResponse<ExportSchema>, HttpException> {
+    let id = personal_data::start_export(state, claims.user_id).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some("The export has started, you will be notified when it is ready".to_string()),
+        payload: Some(ExportSchema { id }),
+    })
+}
+
+/// Download personal data export
+///
+/// Download a finished export of the current user.
+#[utoipa::path(
+  get,
+  path = "/export/{id}",
+  params(
+    ("id" = String, Path, description = "Export id"),
+  ),
+  responses(
+    (status = 200, description = "Zip archive", body = Vec<u8>, content_type = "application/zip"),
+    (status = 404, description = "Export not found or expired"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn download_export(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<ExportParam>,
+) -> Result<Response, HttpException> {
+    let path = http_exception_or!(
+        personal_data::export_file(&state.redis_pool, claims.user_id, &param.id).await?,
+        NotFoundException,
+        "Export not found or expired"
+    );
+    let file = tokio::fs::File::open(&path).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"export-{}.zip\"", param.id),
+            ),
+        ],
+        axum::body::Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response())
+}
+
+/// Erase account
+///
+/// Permanently erase the personal data of the current user. Posts, comments, reactions,
+/// follows, uploads, access tokens and linked identities are deleted, the account is
+/// anonymized and every session is logged out.
+#[utoipa::path(
+  post,
+  path = "/erase",
+  request_body = EraseAccountDto,
+  responses(
+    (status = 200, description = "Account erased"),
+    (status = 401, description = "Invalid password or two-factor authentication code"),
+    (status = 403, description = "Called with a personal access token or an impersonation session"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn erase(
+    State(state): State<Arc<state::AppState>>,
+    cookies: Cookies,
+    claims: Claims,
+    Body(input): Body<EraseAccountDto>,
+) -> Result<HttpResponse<()>, HttpException> {
+    if claims.is_api_token() {
+        http_exception!(
+            ForbiddenException,
+            "Personal access tokens cannot erase the account"
+        );
+    }
+    if claims.is_impersonated() {
+        http_exception!(
+            ForbiddenException,
+            "Impersonation sessions cannot erase the account"
+        );
+    }
+
+    let user = find_current_user(&state, &claims).await?;
+    if !entity::password::verify(&input.password, &user.password) {
+        http_exception!(UnauthorizedException, "Invalid password");
+    }
+    if user.totp_enabled_at.is_some() {
+        let code = input.code.as_deref().unwrap_or_default();
+        if !mfa::verify(&state.db, &state.redis_pool, &user, code).await? {
+            http_exception!(
+                UnauthorizedException,
+                "Invalid two-factor authentication code"
+            );
+        }
+    }
+
+    personal_data::erase(&state, user.id).await?;
+    remove_auth_cookies(&cookies);
+
+    Ok(HttpResponse::Json {
+        message: Some("Your account and personal data have been erased".to_string()),
+        payload: None,
+    })
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ExportSchema {
+    pub id: String,
+}
@@ -0,0 +1,229 @@
+use super::{
+    post::{find_readable_post, AuthorSummary},
+    HttpResponse, JsonResponse, Page,
+};
+use crate::{
+    core::{exception::HttpException, state},
+    dtos::{
+        post_dtos::QueryPostDto,
+        reaction_dtos::{ListReactionsDto, QueryReactionDto},
+    },
+    extractors::{Pagination, Param, Query},
+    guards::Claims,
+    http_exception,
+    services::reaction::{self, ReactionCounts},
+};
+use axum::extract::State;
+use axum_macros::debug_handler;
+use entity::{
+    post_reaction,
+    prelude::{PostReaction, User},
+};
+use sea_orm::{ColumnTrait, EntityTrait, LoaderTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(get_all))
+        .routes(routes!(add_one, remove_one));
+
+    OpenApiRouter::new().nest("/post/{id}/reactions", router)
+}
+
+/// List reactions to a Post
+///
+/// List who reacted to a Post the current user can read and how, newest first.
+#[utoipa::path(
+  get,
+  path = "",
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+    ("page" = Option<u64>, Query, description = "Page number starting at 1, at most 10000"),
+    ("per_page" = Option<u64>, Query, description = "Page size, 20 by default, at most 100"),
+    ("reaction" = Option<String>, Query, description = "Only this reaction"),
+  ),
+  responses(
+    (status = 200, description = "List reactions successfully", body = JsonResponse<ReactionPageSchema>),
+    (status = 400, description = "Paged by cursor"),
+    (status = 404, description = "Post not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn get_all(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryPostDto>,
+    pagination: Pagination,
+    Query(input): Query<ListReactionsDto>,
+) -> Result<HttpResponse<Page<ReactionView>>, HttpException> {
+    let Pagination::Offset { page, per_page } = pagination else {
+        http_exception!(BadRequestException, "Reactions are paged by number");
+    };
+    let post = find_readable_post(&state.db, param.id, &claims).await?;
+
+    let mut query = PostReaction::find().filter(post_reaction::Column::PostId.eq(post.id));
+    if let Some(reaction) = input.reaction {
+        query = query.filter(post_reaction::Column::Reaction.eq(reaction));
+    }
+    let total = query.clone().count(&state.db).await?;
+    let reactions = query
+        .order_by_desc(post_reaction::Column::CreatedAt)
+        .order_by_asc(post_reaction::Column::UserId)
+        .paginate(&state.db, per_page)
+        .fetch_page(page - 1)
+        .await?;
+    let users = reactions.load_one(User, &state.db).await?;
+    let items = reactions
+        .into_iter()
+        .zip(users)
+        .map(|(reaction, user)| ReactionView {
+            reaction,
+            user: user.map(AuthorSummary::from),
+        })
+        .collect();
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(Page {
+            items,
+            total,
+            page: Some(page),
+            per_page,
+            next_cursor: None,
+        }),
+    })
+}
+
+/// React to a Post
+///
+/// Add a reaction of the current user to a Post they can read, reacting twice the same way
+/// changes nothing. Responds with the counts of the reactions to the Post.
+#[utoipa::path(
+  put,
+  path = "/{reaction}",
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+    ("reaction" = String, Path, description = "like, love, laugh, wow, sad or angry"),
+  ),
+  responses(
+    (status = 200, description = "Reaction added successfully", body = JsonResponse<HashMap<String, i64>>),
+    (status = 404, description = "Post not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn add_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryReactionDto>,
+) -> Result<HttpResponse<ReactionCounts>, HttpException> {
+    let post = find_readable_post(&state.db, param.id, &claims).await?;
+    reaction::add(
+        &state.db,
+        &state.redis_pool,
+        post.id,
+        claims.user_id,
+        param.reaction,
+    )
+    .await?;
+
+    counts(&state, post.id).await
+}
+
+/// Remove reaction to a Post
+///
+/// Take back a reaction of the current user. Responds with the counts of the reactions to
+/// the Post.
+#[utoipa::path(
+  delete,
+  path = "/{reaction}",
+  params(
+    ("id" = i32, Path, description = "Post database id"),
+    ("reaction" = String, Path, description = "like, love, laugh, wow, sad or angry"),
+  ),
+  responses(
+    (status = 200, description = "Reaction removed successfully", body = JsonResponse<HashMap<String, i64>>),
+    (status = 404, description = "Post not found"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn remove_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(param): Param<QueryReactionDto>,
+) -> Result<HttpResponse<ReactionCounts>, HttpException> {
+    let post = find_readable_post(&state.db, param.id, &claims).await?;
+    reaction::remove(
+        &state.db,
+        &state.redis_pool,
+        post.id,
+        claims.user_id,
+        param.reaction,
+    )
+    .await?;
+
+    counts(&state, post.id).await
+}
+
+async fn counts(
+    state: &state::AppState,
+    post_id: i32,
+) -> Result<HttpResponse<ReactionCounts>, HttpException> {
+    let counts = reaction::counts(&state.db, &state.redis_pool, &[post_id])
+        .await?
+        .remove(&post_id)
+        .unwrap_or_default();
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(counts),
+    })
+}
+
+/// Who reacted to a post and how
+#[derive(Serialize)]
+struct ReactionView {
+    #[serde(flatten)]
+    reaction: post_reaction::Model,
+    user: Option<AuthorSummary>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReactionSchema {
+    pub post_id: i32,
+    pub user_id: i32,
+    #[schema(example = "like")]
+    pub reaction: String,
+    pub created_at: String,
+    pub user: Option<AuthorSummary>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReactionPageSchema {
+    pub items: Vec<ReactionSchema>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+}
@@ -0,0 +1,55 @@
+use super::{post::visible_to, HttpResponse, JsonResponse};
+use crate::{
+    core::{exception::HttpException, state},
+    guards::Claims,
+    services::tag::{self, TagUsage},
+};
+use axum::extract::State;
+use axum_macros::debug_handler;
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new().routes(routes!(get_all));
+
+    OpenApiRouter::new().nest("/tags", router)
+}
+
+/// Get tags
+///
+/// Tags of the posts the current user can read, with how many of them carry each tag.
+/// The most used come first.
+#[utoipa::path(
+  get,
+  path = "",
+  responses(
+    (status = 200, description = "Tags", body = JsonResponse<Vec<TagUsageSchema>>),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::POST_TAG
+)]
+#[debug_handler]
+async fn get_all(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+) -> Result<HttpResponse<Vec<TagUsage>>, HttpException> {
+    let tags = tag::usage(&state.db, visible_to(claims.user_id)).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(tags),
+    })
+}
+
+#[derive(Serialize, ToSchema)]
+struct TagUsageSchema {
+    #[schema(example = "rust")]
+    pub name: String,
+    pub count: i64,
+}
@@ -0,0 +1,208 @@
+use super::{HttpResponse, JsonResponse};
+use crate::{
+    core::{exception::HttpException, state},
+    dtos::user_dtos::{CreateTokenDto, TokenParam},
+    extractors::{Body, Param},
+    guards::Claims,
+    http_exception, http_exception_or,
+    services::api_token,
+};
+use axum::extract::State;
+use axum_macros::debug_handler;
+use entity::{personal_access_token, prelude::PersonalAccessToken};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(get_all, create_one))
+        .routes(routes!(delete_one));
+
+    OpenApiRouter::new().nest("/user/tokens", router)
+}
+
+/// List personal access tokens
+///
+/// List the personal access tokens of the current user, the tokens themselves are never returned.
+#[utoipa::path(
+  get,
+  path = "",
+  responses(
+    (status = 200, description = "List tokens successfully", body = JsonResponse<Vec<TokenSchema>>)
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::TOKEN_TAG
+)]
+#[debug_handler]
+async fn get_all(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+) -> Result<HttpResponse<Vec<personal_access_token::Model>>, HttpException> {
+    let tokens = PersonalAccessToken::find()
+        .filter(personal_access_token::Column::UserId.eq(claims.user_id))
+        .order_by_desc(personal_access_token::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(tokens),
+    })
+}
+
+/// Create personal access token
+///
+/// Create a token to be sent in the `PRIVATE-TOKEN` header. The token is only returned once.
+#[utoipa::path(
+  post,
+  path = "",
+  request_body = CreateTokenDto,
+  responses(
+    (status = 200, description = "Token created successfully", body = JsonResponse<NewTokenSchema>),
+    (status = 400, description = "Invalid scopes or expiry"),
+    (status = 403, description = "Called with a personal access token or an impersonation session"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::TOKEN_TAG
+)]
+#[debug_handler]
+async fn create_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Body(input): Body<CreateTokenDto>,
+) -> Result<HttpResponse<NewToken>, HttpException> {
+    if claims.is_api_token() {
+        http_exception!(
+            ForbiddenException,
+            "Personal access tokens cannot create other tokens"
+        );
+    }
+    if claims.is_impersonated() {
+        http_exception!(
+            ForbiddenException,
+            "Impersonation sessions cannot create tokens"
+        );
+    }
+
+    let (token, token_hash) = api_token::generate();
+    let scopes = input
+        .scopes
+        .unwrap_or_else(|| vec![api_token::SCOPE_READ.to_string()]);
+    let expires_at = input
+        .expires_in_days
+        .map(|days| chrono::Utc::now() + chrono::Duration::days(days));
+
+    let model = personal_access_token::ActiveModel {
+        name: Set(input.name),
+        token_hash: Set(token_hash),
+        scopes: Set(scopes.join(" ")),
+        expires_at: Set(expires_at),
+        user_id: Set(claims.user_id),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Json {
+        message: Some("Make sure to copy the token now, it will not be shown again".to_string()),
+        payload: Some(NewToken { model, token }),
+    })
+}
+
+/// Revoke personal access token
+///
+/// Revoke one of the current user's personal access tokens.
+#[utoipa::path(
+  delete,
+  path = "/{id}",
+  responses(
+    (status = 200, description = "Token revoked successfully"),
+    (status = 403, description = "Called with an impersonation session"),
+    (status = 404, description = "Token not found")
+  ),
+  params(
+    ("id" = i32, Path, description = "Token database id"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::TOKEN_TAG
+)]
+#[debug_handler]
+async fn delete_one(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Param(input): Param<TokenParam>,
+) -> Result<HttpResponse<()>, HttpException> {
+    if claims.is_impersonated() {
+        http_exception!(
+            ForbiddenException,
+            "Impersonation sessions cannot revoke tokens"
+        );
+    }
+
+    let token = http_exception_or!(
+        PersonalAccessToken::find_by_id(input.id)
+            .filter(personal_access_token::Column::UserId.eq(claims.user_id))
+            .one(&state.db)
+            .await?,
+        NotFoundException,
+        format!("No token found with id {}", input.id)
+    );
+    token.delete(&state.db).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("The token {} has been revoked", input.id)),
+        payload: None,
+    })
+}
+
+#[derive(Serialize)]
+struct NewToken {
+    #[serde(flatten)]
+    model: personal_access_token::Model,
+    /// plain token, only available right after creation
+    token: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct TokenSchema {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    #[schema(example = "read write")]
+    pub scopes: String,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct NewTokenSchema {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub scopes: String,
+    pub expires_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub token: String,
+}
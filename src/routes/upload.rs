@@ -1,5 +1,10 @@
 use super::HttpResponse;
-use crate::core::{exception::HttpException, state};
+use crate::{
+    core::{exception::HttpException, state},
+    guards::Claims,
+    http_exception_or,
+    services::upload,
+};
 use axum::{
     extract::DefaultBodyLimit,
     http::StatusCode,
@@ -8,16 +13,11 @@ use axum::{
 use axum_macros::debug_handler;
 use axum_typed_multipart::{BaseMultipart, FieldData, TryFromMultipart, TypedMultipartError};
 use serde::Serialize;
-use std::{
-    path::{Path, PathBuf},
-    sync::Arc,
-};
+use std::{path::PathBuf, sync::Arc};
 use tempfile::NamedTempFile;
 use utoipa::ToSchema;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
-const UPLOADS_DIRECTORY: &str = "uploads";
-
 pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
     let router = OpenApiRouter::new()
         .routes(routes!(upload_handler))
@@ -47,9 +47,17 @@ struct FileUpload {
 )]
 #[debug_handler]
 async fn upload_handler(
+    claims: Claims,
     input: SelfTypedMultipart<FileUpload>,
 ) -> Result<HttpResponse<PathBuf>, HttpException> {
-    let path = Path::new(UPLOADS_DIRECTORY).join(&input.name);
+    let path = http_exception_or!(
+        upload::file_path(claims.user_id, &input.name),
+        BadRequestException,
+        "Invalid file name"
+    );
+    tokio::fs::create_dir_all(upload::user_dir(claims.user_id))
+        .await
+        .map_err(|err| HttpException::InternalServerErrorException(Some(err.to_string())))?;
     input
         .data
         .file
@@ -1,31 +1,73 @@
 use super::{HttpResponse, JsonResponse};
 use crate::{
     core::{config, exception::HttpException, state},
-    dtos::user_dtos::{CreateUserDto, DeleteUserDto, DeleteUserParam, LoginUserDto, RedirectParam},
-    extractors::{Body, Param, Query},
-    guards::jwt_encode,
+    dtos::user_dtos::{
+        ChangePasswordDto, CreateUserDto, DeleteUserParam, ForgotPasswordDto, LoginMfaDto,
+        LoginUserDto, RedirectParam, RefreshTokenDto, ResetPasswordDto, SessionParam,
+        UpdateProfileDto, VerifyEmailDto,
+    },
+    extractors::{Body, ClientInfo, Param, Query},
+    guards::Claims,
     http_exception, http_exception_or,
+    services::{
+        audit::{self, actions},
+        login_throttle,
+        mailer::Mail,
+        mfa,
+        one_time_token::{self, Purpose},
+        password,
+        rbac::{self, permissions},
+        session::{self, Session, TokenPair},
+        token_denylist,
+    },
+};
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
 };
-use axum::extract::State;
 use axum_macros::debug_handler;
-use entity::{post, prelude::Post, prelude::User, user};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+use entity::{post, prelude::Post, prelude::User, soft_delete::SoftDelete, user};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tower_cookies::{Cookie, Cookies};
+use tower_cookies::{cookie::SameSite, Cookie, Cookies};
 use utoipa::ToSchema;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
+// the refresh cookie is only sent to the refresh endpoint
+const REFRESH_COOKIE_PATH: &str = "/api/v1/user/refresh";
+
 pub fn public_route() -> OpenApiRouter<Arc<state::AppState>> {
     let router = OpenApiRouter::new()
         .routes(routes!(create_one))
-        .routes(routes!(login));
+        .routes(routes!(login))
+        .routes(routes!(login_mfa))
+        .routes(routes!(refresh))
+        .routes(routes!(forgot_password))
+        .routes(routes!(reset_password))
+        .routes(routes!(verify_email));
 
     OpenApiRouter::new().nest("/user", router)
 }
 
 pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
-    let router = OpenApiRouter::new().routes(routes!(delete_one, signout));
+    let router = OpenApiRouter::new()
+        .routes(routes!(delete_one))
+        .routes(routes!(signout))
+        .routes(routes!(list_sessions, delete_sessions))
+        .routes(routes!(delete_session))
+        .routes(routes!(change_password));
+
+    OpenApiRouter::new().nest("/user", router)
+}
+
+/// Protected routes that stay available to users who have not verified their email yet
+pub fn unverified_route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(resend_verification))
+        .routes(routes!(get_me, patch_me));
 
     OpenApiRouter::new().nest("/user", router)
 }
@@ -33,6 +75,7 @@ pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
 /// Create new User
 ///
 /// Tries to create a new User or fails with 409 conflict if already exists.
+/// A verification link is mailed to the new email address.
 #[utoipa::path(
   post,
   path = "",
@@ -47,35 +90,38 @@ pub fn protected_route() -> OpenApiRouter<Arc<state::AppState>> {
 pub(crate) async fn create_one(
     State(state): State<Arc<state::AppState>>,
     cookies: Cookies,
+    client: ClientInfo,
     Body(input): Body<CreateUserDto>,
-) -> Result<HttpResponse<user::Model>, HttpException> {
+) -> Result<HttpResponse<AuthPayload>, HttpException> {
+    let txn = state.db.begin().await?;
     let user = user::ActiveModel {
         name: Set(input.name),
         email: Set(input.email),
         password: Set(input.password),
         ..Default::default()
     }
-    .insert(&state.db)
+    .insert(&txn)
     .await?;
+    rbac::assign_role(&txn, user.id, rbac::ROLE_USER).await?;
+    txn.commit().await?;
 
-    let config = config::Config::global();
-    let token = jwt_encode(user.id, config.jwt_keys().encoding())
-        .map_err(|_| HttpException::UnauthorizedException(None))?;
-    let cookie = Cookie::build((config.app_auth_key(), token))
-        .secure(true)
-        .http_only(true)
-        .build();
-    cookies.add(cookie);
+    if let Err(err) = send_verification_mail(&state, &user).await {
+        tracing::error!(user_id = user.id, %err, "failed to send verification mail");
+    }
+    let tokens = start_session(&state, &cookies, &client, user.id).await?;
 
     Ok(HttpResponse::Json {
         message: None,
-        payload: Some(user),
+        payload: Some(AuthPayload { user, tokens }),
     })
 }
 
 /// User Login
 ///
-/// If successful, identity credentials are returned
+/// If successful, identity credentials are returned, both as cookies and in the body for
+/// clients that cannot use cookies.
+/// Users with two-factor authentication get `{ mfaRequired, mfaToken, expiresIn }` instead,
+/// the token is exchanged for the credentials at `/user/login/mfa`.
 #[utoipa::path(
   post,
   path = "/login",
@@ -83,6 +129,8 @@ pub(crate) async fn create_one(
   responses(
     (status = 200, description = "User created successfully", headers(("Set-Cookie" = String, description = "identity credentials")), body = JsonResponse<UserSchema>),
     (status = 400, description = "User not found"),
+    (status = 403, description = "Account is disabled"),
+    (status = 429, description = "Too many failed attempts", headers(("Retry-After" = u64, description = "seconds until the lockout ends"))),
   ),
   tag = crate::api_doc::USER_TAG
 )]
@@ -90,38 +138,518 @@ pub(crate) async fn create_one(
 pub(crate) async fn login(
     State(state): State<Arc<state::AppState>>,
     cookies: Cookies,
+    client: ClientInfo,
     Body(input): Body<LoginUserDto>,
-) -> Result<HttpResponse<user::Model>, HttpException> {
+) -> Result<Response, HttpException> {
+    let ip = client.ip.as_deref();
+    if let Some(seconds) = login_throttle::locked_for(&state.redis_pool, &input.email, ip).await? {
+        return Ok(login_throttle::locked_out(seconds));
+    }
+
+    let user = User::find_active()
+        .filter(user::Column::Email.eq(&input.email))
+        .one(&state.db)
+        .await?;
+    let Some(user) = user else {
+        login_throttle::record_failure(&state.redis_pool, &input.email, ip).await?;
+        http_exception!(
+            NotFoundException,
+            format!("No user found with email {}", &input.email)
+        );
+    };
+
+    if !password::verify(&state.db, &user, &input.password).await? {
+        login_throttle::record_failure(&state.redis_pool, &input.email, ip).await?;
+        http_exception!(UnauthorizedException, "Invalid email or password");
+    }
+    if user.disabled_at.is_some() {
+        http_exception!(ForbiddenException, "Account is disabled");
+    }
+
+    if user.totp_enabled_at.is_some() {
+        // the counters are reset once the second factor is accepted as well
+        let mfa_token =
+            one_time_token::issue(&state.redis_pool, Purpose::MfaPending, user.id).await?;
+
+        return Ok(HttpResponse::Json {
+            message: Some("Two-factor authentication code required".to_string()),
+            payload: Some(LoginPayload::MfaRequired(MfaChallenge {
+                mfa_required: true,
+                mfa_token,
+                expires_in: Purpose::MfaPending.ttl(),
+            })),
+        }
+        .into_response());
+    }
+
+    login_throttle::record_success(&state.redis_pool, &user.email).await?;
+    let tokens = start_session(&state, &cookies, &client, user.id).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(LoginPayload::Authenticated(AuthPayload { user, tokens })),
+    }
+    .into_response())
+}
+
+/// Complete two-factor login
+///
+/// Exchange the `mfaToken` returned by `/user/login` and a TOTP or recovery code for the
+/// identity credentials.
+#[utoipa::path(
+  post,
+  path = "/login/mfa",
+  request_body = LoginMfaDto,
+  responses(
+    (status = 200, description = "User logged in successfully", headers(("Set-Cookie" = String, description = "identity credentials")), body = JsonResponse<UserSchema>),
+    (status = 401, description = "Invalid or expired mfa token, or invalid code"),
+    (status = 429, description = "Too many failed attempts", headers(("Retry-After" = u64, description = "seconds until the lockout ends"))),
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn login_mfa(
+    State(state): State<Arc<state::AppState>>,
+    cookies: Cookies,
+    client: ClientInfo,
+    Body(input): Body<LoginMfaDto>,
+) -> Result<Response, HttpException> {
+    // the token is only used up once the code was accepted, so a typo does not require
+    // entering the password again
+    let user_id = http_exception_or!(
+        one_time_token::peek(&state.redis_pool, Purpose::MfaPending, &input.mfa_token).await?,
+        UnauthorizedException,
+        "Invalid or expired mfa token"
+    );
     let user = http_exception_or!(
-        User::find()
-            .filter(user::Column::Email.eq(&input.email))
+        User::find_active_by_id(user_id)
             .one(&state.db)
-            .await?,
-        NotFoundException,
-        format!("No user found with email {}", &input.email)
+            .await?
+            .filter(|user| user.disabled_at.is_none()),
+        UnauthorizedException,
+        "Invalid or expired mfa token"
     );
 
-    // verify password
-    let is_valid_password = bcrypt::verify(&input.password, &user.password).unwrap_or(false);
-    if !is_valid_password {
-        http_exception!(UnauthorizedException, "Invalid email or password");
+    let ip = client.ip.as_deref();
+    if let Some(seconds) = login_throttle::locked_for(&state.redis_pool, &user.email, ip).await? {
+        return Ok(login_throttle::locked_out(seconds));
+    }
+    if !mfa::verify(&state.db, &state.redis_pool, &user, &input.code).await? {
+        login_throttle::record_failure(&state.redis_pool, &user.email, ip).await?;
+        http_exception!(
+            UnauthorizedException,
+            "Invalid two-factor authentication code"
+        );
+    }
+    // a concurrent request may have used the token in the meantime
+    if one_time_token::consume(&state.redis_pool, Purpose::MfaPending, &input.mfa_token)
+        .await?
+        .is_none()
+    {
+        http_exception!(UnauthorizedException, "Invalid or expired mfa token");
+    }
+
+    login_throttle::record_success(&state.redis_pool, &user.email).await?;
+    let tokens = start_session(&state, &cookies, &client, user.id).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(AuthPayload { user, tokens }),
     }
+    .into_response())
+}
 
+/// Refresh credentials
+///
+/// Exchange a refresh token for a new access token and refresh token.
+/// A refresh token can only be used once, replaying it revokes the whole session.
+#[utoipa::path(
+  post,
+  path = "/refresh",
+  request_body = RefreshTokenDto,
+  responses(
+    (status = 200, description = "Credentials refreshed successfully", headers(("Set-Cookie" = String, description = "identity credentials")), body = JsonResponse<TokenSchema>),
+    (status = 401, description = "Invalid, expired or revoked refresh token"),
+    (status = 403, description = "Impersonation sessions cannot be refreshed"),
+    (status = 409, description = "Session kept changing during the refresh"),
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn refresh(
+    State(state): State<Arc<state::AppState>>,
+    cookies: Cookies,
+    Body(input): Body<RefreshTokenDto>,
+) -> Result<HttpResponse<TokenPair>, HttpException> {
     let config = config::Config::global();
-    let token = jwt_encode(user.id, config.jwt_keys().encoding())
-        .map_err(|_| HttpException::UnauthorizedException(None))?;
-    let cookie = Cookie::build((config.app_auth_key(), token))
-        .secure(true)
-        .http_only(true)
-        .build();
-    cookies.add(cookie);
+    let refresh_token = http_exception_or!(
+        input.refresh_token.or_else(|| cookies
+            .get(&config.app_refresh_key())
+            .map(|c| c.value().to_string())),
+        UnauthorizedException,
+        "Missing refresh token"
+    );
+
+    let tokens = match session::rotate(&state, &refresh_token).await {
+        Ok((_, tokens)) => tokens,
+        Err(err) => {
+            remove_auth_cookies(&cookies);
+            return Err(err);
+        }
+    };
+    set_auth_cookies(&cookies, &tokens);
 
     Ok(HttpResponse::Json {
         message: None,
+        payload: Some(tokens),
+    })
+}
+
+/// Request password reset
+///
+/// Mail a password reset link to the user. Always succeeds so the response does not reveal
+/// whether the email is registered.
+#[utoipa::path(
+  post,
+  path = "/password/forgot",
+  request_body = ForgotPasswordDto,
+  responses(
+    (status = 200, description = "Reset link sent if the email is registered"),
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn forgot_password(
+    State(state): State<Arc<state::AppState>>,
+    Body(input): Body<ForgotPasswordDto>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let user = User::find_active()
+        .filter(user::Column::Email.eq(&input.email))
+        .one(&state.db)
+        .await?;
+
+    if let Some(user) = user {
+        let config = config::Config::global();
+        let token =
+            one_time_token::issue(&state.redis_pool, Purpose::PasswordReset, user.id).await?;
+        let mail = Mail {
+            to: user.email,
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Hi {},\n\nUse the link below to choose a new password, it expires in {} minutes.\n\n{}/reset-password?token={}\n\nIf you did not request a password reset, you can ignore this email.",
+                user.name,
+                Purpose::PasswordReset.ttl() / 60,
+                config.app_url(),
+                token
+            ),
+        };
+        if let Err(err) = state.mailer.send(mail).await {
+            tracing::error!(user_id = user.id, %err, "failed to send password reset mail");
+        }
+    }
+
+    Ok(HttpResponse::Json {
+        message: Some(
+            "If the email is registered, a password reset link has been sent".to_string(),
+        ),
+        payload: None,
+    })
+}
+
+/// Reset password
+///
+/// Set a new password with a reset token. The token can only be used once and every
+/// session of the user is logged out.
+#[utoipa::path(
+  post,
+  path = "/password/reset",
+  request_body = ResetPasswordDto,
+  responses(
+    (status = 200, description = "Password reset successfully"),
+    (status = 400, description = "Invalid or expired reset token"),
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn reset_password(
+    State(state): State<Arc<state::AppState>>,
+    Body(input): Body<ResetPasswordDto>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let user_id = http_exception_or!(
+        one_time_token::consume(&state.redis_pool, Purpose::PasswordReset, &input.token).await?,
+        BadRequestException,
+        "Invalid or expired reset token"
+    );
+    let user = http_exception_or!(
+        User::find_active_by_id(user_id).one(&state.db).await?,
+        BadRequestException,
+        "Invalid or expired reset token"
+    );
+
+    // hashed by `before_save`
+    let mut user: user::ActiveModel = user.into();
+    user.password = Set(input.password);
+    user.update(&state.db).await?;
+    session::terminate_all(&state, user_id).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some("Your password has been reset, please log in again".to_string()),
+        payload: None,
+    })
+}
+
+/// Verify email
+///
+/// Mark the email of the user as verified with the token from the verification mail. The mail
+/// links to `/verify-email` on the frontend, which passes the token on.
+#[utoipa::path(
+  get,
+  path = "/verify",
+  params(
+    ("token" = String, Query, description = "Token from the verification mail"),
+  ),
+  responses(
+    (status = 200, description = "Email verified successfully"),
+    (status = 400, description = "Invalid or expired verification token"),
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn verify_email(
+    State(state): State<Arc<state::AppState>>,
+    Query(input): Query<VerifyEmailDto>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let user_id = http_exception_or!(
+        one_time_token::consume(&state.redis_pool, Purpose::EmailVerification, &input.token)
+            .await?,
+        BadRequestException,
+        "Invalid or expired verification token"
+    );
+    let user = http_exception_or!(
+        User::find_active_by_id(user_id).one(&state.db).await?,
+        BadRequestException,
+        "Invalid or expired verification token"
+    );
+
+    if user.email_verified_at.is_none() {
+        let mut user: user::ActiveModel = user.into();
+        user.email_verified_at = Set(Some(chrono::Utc::now()));
+        user.update(&state.db).await?;
+    }
+
+    Ok(HttpResponse::Json {
+        message: Some("Your email has been verified".to_string()),
+        payload: None,
+    })
+}
+
+/// Resend verification email
+///
+/// Mail a new verification link to the current user, the previous link stops working.
+#[utoipa::path(
+  post,
+  path = "/verify/resend",
+  responses(
+    (status = 200, description = "Verification mail sent successfully"),
+    (status = 400, description = "Email already verified"),
+    (status = 401, description = "Unauthorized"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn resend_verification(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+) -> Result<HttpResponse<()>, HttpException> {
+    let user = http_exception_or!(
+        User::find_active_by_id(claims.user_id)
+            .one(&state.db)
+            .await?,
+        NotFoundException,
+        format!("No user found with id {}", claims.user_id)
+    );
+    if user.email_verified_at.is_some() {
+        http_exception!(BadRequestException, "Email already verified");
+    }
+
+    send_verification_mail(&state, &user).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!(
+            "A verification link has been sent to {}",
+            user.email
+        )),
+        payload: None,
+    })
+}
+
+/// Current User
+///
+/// Get the profile of the current user.
+#[utoipa::path(
+  get,
+  path = "/me",
+  responses(
+    (status = 200, description = "Query profile successfully", body = JsonResponse<ProfileSchema>),
+    (status = 401, description = "Unauthorized"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn get_me(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+) -> Result<HttpResponse<user::Model>, HttpException> {
+    let user = find_current_user(&state, &claims).await?;
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(user),
+    })
+}
+
+/// Update current User
+///
+/// Update the name or email of the current user. A new email has to be verified again,
+/// a verification link is mailed to it.
+#[utoipa::path(
+  patch,
+  path = "/me",
+  request_body = UpdateProfileDto,
+  responses(
+    (status = 200, description = "Profile updated successfully", body = JsonResponse<ProfileSchema>),
+    (status = 400, description = "Name or email already taken"),
+    (status = 401, description = "Unauthorized"),
+    (status = 403, description = "Email changed with a personal access token or an impersonation session"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn patch_me(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Body(input): Body<UpdateProfileDto>,
+) -> Result<HttpResponse<user::Model>, HttpException> {
+    let current = find_current_user(&state, &claims).await?;
+    let email_changed = input
+        .email
+        .as_ref()
+        .is_some_and(|email| *email != current.email);
+    if email_changed && claims.is_api_token() {
+        http_exception!(
+            ForbiddenException,
+            "Personal access tokens cannot change the email"
+        );
+    }
+    if email_changed && claims.is_impersonated() {
+        http_exception!(
+            ForbiddenException,
+            "Impersonation sessions cannot change the email"
+        );
+    }
+
+    let mut user: user::ActiveModel = current.into();
+    if let Some(name) = input.name {
+        user.name = Set(name);
+    }
+    if let (true, Some(email)) = (email_changed, input.email) {
+        user.email = Set(email);
+        user.email_verified_at = Set(None);
+    }
+    let user = user.update(&state.db).await?;
+
+    if email_changed {
+        if let Err(err) = send_verification_mail(&state, &user).await {
+            tracing::error!(user_id = user.id, %err, "failed to send verification mail");
+        }
+    }
+
+    Ok(HttpResponse::Json {
+        message: email_changed
+            .then(|| format!("A verification link has been sent to {}", user.email)),
         payload: Some(user),
     })
 }
 
+/// Change password
+///
+/// Change the password of the current user. Every other session is logged out.
+#[utoipa::path(
+  put,
+  path = "/me/password",
+  request_body = ChangePasswordDto,
+  responses(
+    (status = 200, description = "Password changed successfully"),
+    (status = 401, description = "Unauthorized or wrong current password"),
+    (status = 403, description = "Called with a personal access token or an impersonation session"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn change_password(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+    Body(input): Body<ChangePasswordDto>,
+) -> Result<HttpResponse<()>, HttpException> {
+    if claims.is_api_token() {
+        http_exception!(
+            ForbiddenException,
+            "Personal access tokens cannot change the password"
+        );
+    }
+    if claims.is_impersonated() {
+        http_exception!(
+            ForbiddenException,
+            "Impersonation sessions cannot change the password"
+        );
+    }
+
+    let user = find_current_user(&state, &claims).await?;
+    if !entity::password::verify(&input.current_password, &user.password) {
+        http_exception!(UnauthorizedException, "Invalid current password");
+    }
+
+    // hashed by `before_save`
+    let mut user: user::ActiveModel = user.into();
+    user.password = Set(input.new_password);
+    user.update(&state.db).await?;
+
+    let others: Vec<Session> = session::list(&state.redis_pool, claims.user_id)
+        .await?
+        .into_iter()
+        .filter(|session| session.id != claims.sid)
+        .collect();
+    session::terminate(&state, &others).await?;
+
+    Ok(HttpResponse::Json {
+        message: Some(format!(
+            "Your password has been changed, {} other sessions have been logged out",
+            others.len()
+        )),
+        payload: None,
+    })
+}
+
 /// User Logout
 ///
 /// User logout
@@ -134,39 +662,165 @@ pub(crate) async fn login(
     (status = 401, description = "Unauthorized"),
   ),
   security(
-    ("cookie_security" = [])
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
   ),
   tag = crate::api_doc::USER_TAG
 )]
 #[debug_handler]
 async fn signout(
+    State(state): State<Arc<state::AppState>>,
     cookies: Cookies,
+    claims: Claims,
     Body(input): Body<RedirectParam>,
 ) -> Result<HttpResponse<()>, HttpException> {
-    let config = config::Config::global();
-    cookies.remove(Cookie::from(config.app_auth_key()));
+    if let Some(session) = session::find(&state.redis_pool, &claims.sid).await? {
+        session::terminate(&state, &[session]).await?;
+    }
+    if !claims.is_api_token() {
+        token_denylist::revoke(&state.redis_pool, &claims).await?;
+    }
+    remove_auth_cookies(&cookies);
 
     let uri = input.uri.unwrap_or("/login".to_string());
     Ok(HttpResponse::RedirectTo { uri })
 }
 
+/// List active sessions
+///
+/// List the active login sessions of the current user.
+#[utoipa::path(
+  get,
+  path = "/sessions",
+  responses(
+    (status = 200, description = "List sessions successfully", body = JsonResponse<Vec<SessionSchema>>),
+    (status = 401, description = "Unauthorized"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn list_sessions(
+    State(state): State<Arc<state::AppState>>,
+    claims: Claims,
+) -> Result<HttpResponse<Vec<SessionSchema>>, HttpException> {
+    let sessions = session::list(&state.redis_pool, claims.user_id)
+        .await?
+        .into_iter()
+        .map(|session| SessionSchema::new(session, &claims.sid))
+        .collect();
+
+    Ok(HttpResponse::Json {
+        message: None,
+        payload: Some(sessions),
+    })
+}
+
+/// Revoke session by id
+///
+/// Log out one of the current user's sessions and disconnect its sockets.
+#[utoipa::path(
+  delete,
+  path = "/sessions/{id}",
+  responses(
+    (status = 200, description = "Session revoked successfully"),
+    (status = 401, description = "Unauthorized"),
+    (status = 404, description = "Session not found"),
+  ),
+  params(
+    ("id" = String, Path, description = "Session id"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn delete_session(
+    State(state): State<Arc<state::AppState>>,
+    cookies: Cookies,
+    claims: Claims,
+    Param(input): Param<SessionParam>,
+) -> Result<HttpResponse<()>, HttpException> {
+    let session = http_exception_or!(
+        session::find(&state.redis_pool, &input.id)
+            .await?
+            .filter(|session| session.user_id == claims.user_id),
+        NotFoundException,
+        format!("No session found with id {}", input.id)
+    );
+    session::terminate(&state, &[session]).await?;
+    if input.id == claims.sid {
+        remove_auth_cookies(&cookies);
+    }
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("The session {} has been revoked", input.id)),
+        payload: None,
+    })
+}
+
+/// Revoke all sessions
+///
+/// Log out every session of the current user, including the current one.
+#[utoipa::path(
+  delete,
+  path = "/sessions",
+  responses(
+    (status = 200, description = "Sessions revoked successfully"),
+    (status = 401, description = "Unauthorized"),
+  ),
+  security(
+    ("cookie_security" = []),
+    ("bearer_security" = []),
+    ("header_security" = [])
+  ),
+  tag = crate::api_doc::USER_TAG
+)]
+#[debug_handler]
+async fn delete_sessions(
+    State(state): State<Arc<state::AppState>>,
+    cookies: Cookies,
+    claims: Claims,
+) -> Result<HttpResponse<()>, HttpException> {
+    let sessions = session::terminate_all(&state, claims.user_id).await?;
+    remove_auth_cookies(&cookies);
+
+    Ok(HttpResponse::Json {
+        message: Some(format!("{} sessions have been revoked", sessions.len())),
+        payload: None,
+    })
+}
+
 /// Delete User by id
 ///
 /// Delete User by id. Returns either 200 success of 404 with RespError if User is not found.
+///
+/// The user is soft deleted together with their posts, and both can be restored by an admin
+/// until they are purged. Posts always go with their user.
 #[utoipa::path(
 	delete,
 	path = "/{id}",
 	responses(
 		(status = 200, description = "User delete done successfully"),
 		(status = 401, description = "Unauthorized to delete User"),
+		(status = 403, description = "Deleting another user requires the users:delete permission"),
 		(status = 404, description = "User not found")
 		),
 	params(
 		("id" = i32, Path, description = "User database id"),
-		("thoroughly" = Option<bool>, Query, description = "Whether to completely delete all user related information, default value is false")
 	),
 	security(
-		("cookie_security" = [])
+		("cookie_security" = []),
+		("bearer_security" = []),
+		("header_security" = [])
 	),
   tag = crate::api_doc::USER_TAG
 )]
@@ -174,23 +828,54 @@ async fn signout(
 pub(crate) async fn delete_one(
     State(state): State<Arc<state::AppState>>,
     cookies: Cookies,
+    claims: Claims,
+    client: ClientInfo,
     Param(input): Param<DeleteUserParam>,
-    Query(dto): Query<DeleteUserDto>,
 ) -> Result<HttpResponse<()>, HttpException> {
-    let thoroughly = dto.thoroughly.unwrap_or(false);
+    let is_self = input.id == claims.user_id;
+    if !is_self && !claims.has_permission(permissions::USERS_DELETE) {
+        http_exception!(
+            ForbiddenException,
+            format!("Missing permission {}", permissions::USERS_DELETE)
+        );
+    }
+
+    let now = chrono::Utc::now();
     let txn = state.db.begin().await?;
-    User::delete_by_id(input.id).exec(&txn).await?;
-    if thoroughly {
-        // All information under this user needs to be deleted
-        // delete posts
-        Post::delete_many()
-            .filter(post::Column::UserId.eq(input.id))
-            .exec(&txn)
-            .await?;
+    let deleted = User::update_many()
+        .col_expr(user::Column::DeletedAt, Expr::value(now))
+        .filter(user::Column::Id.eq(input.id))
+        .filter(user::Column::DeletedAt.is_null())
+        .exec(&txn)
+        .await?;
+    if deleted.rows_affected == 0 {
+        http_exception!(
+            NotFoundException,
+            format!("No user found with id {}", input.id)
+        );
     }
+    // the posts share the deletion time of the user so restoring it brings them back, they
+    // are purged along with it
+    Post::update_many()
+        .col_expr(post::Column::DeletedAt, Expr::value(now))
+        .filter(post::Column::UserId.eq(input.id))
+        .filter(post::Column::DeletedAt.is_null())
+        .exec(&txn)
+        .await?;
+    audit::record(
+        &txn,
+        claims.user_id,
+        actions::USER_DELETE,
+        Some(input.id),
+        None,
+        client.ip,
+    )
+    .await?;
     txn.commit().await?;
-    let config = config::Config::global();
-    cookies.remove(Cookie::from(config.app_auth_key()));
+    session::terminate_all(&state, input.id).await?;
+    if is_self {
+        remove_auth_cookies(&cookies);
+    }
 
     Ok(HttpResponse::Json {
         message: Some(format!(
@@ -201,13 +886,181 @@ pub(crate) async fn delete_one(
     })
 }
 
+/// Start a new session for the user and hand the credentials out as cookies
+pub(crate) async fn start_session(
+    state: &state::AppState,
+    cookies: &Cookies,
+    client: &ClientInfo,
+    user_id: i32,
+) -> Result<TokenPair, HttpException> {
+    let (_, tokens) = session::create(state, user_id, client).await?;
+    set_auth_cookies(cookies, &tokens);
+
+    Ok(tokens)
+}
+
+pub(crate) async fn find_current_user(
+    state: &state::AppState,
+    claims: &Claims,
+) -> Result<user::Model, HttpException> {
+    let user = http_exception_or!(
+        User::find_active_by_id(claims.user_id)
+            .one(&state.db)
+            .await?,
+        NotFoundException,
+        format!("No user found with id {}", claims.user_id)
+    );
+
+    Ok(user)
+}
+
+/// Mail a fresh verification link to the email of the user
+pub(crate) async fn send_verification_mail(
+    state: &state::AppState,
+    user: &user::Model,
+) -> Result<(), HttpException> {
+    let token =
+        one_time_token::issue(&state.redis_pool, Purpose::EmailVerification, user.id).await?;
+    let mail = Mail {
+        to: user.email.clone(),
+        subject: "Verify your email".to_string(),
+        body: format!(
+            "Hi {},\n\nPlease confirm your email address by opening the link below, it expires in {} hours.\n\n{}/verify-email?token={}",
+            user.name,
+            Purpose::EmailVerification.ttl() / 3600,
+            config::Config::global().app_url(),
+            token
+        ),
+    };
+    state
+        .mailer
+        .send(mail)
+        .await
+        .map_err(|err| HttpException::InternalServerErrorException(Some(err.to_string())))
+}
+
+fn set_auth_cookies(cookies: &Cookies, tokens: &TokenPair) {
+    let config = config::Config::global();
+    let access = Cookie::build((
+        config.app_auth_key().to_string(),
+        tokens.access_token.clone(),
+    ))
+    .path("/")
+    .secure(true)
+    .http_only(true)
+    .build();
+    let refresh = Cookie::build((config.app_refresh_key(), tokens.refresh_token.clone()))
+        .path(REFRESH_COOKIE_PATH)
+        .same_site(SameSite::Strict)
+        .secure(true)
+        .http_only(true)
+        .build();
+    cookies.add(access);
+    cookies.add(refresh);
+}
+
+pub(crate) fn remove_auth_cookies(cookies: &Cookies) {
+    let config = config::Config::global();
+    cookies.remove(
+        Cookie::build(config.app_auth_key().to_string())
+            .path("/")
+            .build(),
+    );
+    cookies.remove(
+        Cookie::build(config.app_refresh_key())
+            .path(REFRESH_COOKIE_PATH)
+            .build(),
+    );
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SessionSchema {
+    pub id: String,
+    pub device_id: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    /// unix timestamp in seconds
+    pub created_at: i64,
+    /// unix timestamp in seconds
+    pub last_seen: i64,
+    /// whether this is the session of the current request
+    pub current: bool,
+    /// admin who opened this session by impersonating the user
+    pub impersonator_id: Option<i32>,
+}
+
+impl SessionSchema {
+    pub(crate) fn new(session: Session, current_sid: &str) -> Self {
+        Self {
+            current: session.id == current_sid,
+            id: session.id,
+            device_id: session.device_id,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            created_at: session.created_at,
+            last_seen: session.last_seen,
+            impersonator_id: session.impersonator_id,
+        }
+    }
+}
+
+/// User with the credentials of the session that was just started
+#[derive(Serialize)]
+pub(crate) struct AuthPayload {
+    #[serde(flatten)]
+    pub user: user::Model,
+    #[serde(flatten)]
+    pub tokens: TokenPair,
+}
+
+/// Result of a password login
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum LoginPayload {
+    Authenticated(AuthPayload),
+    MfaRequired(MfaChallenge),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MfaChallenge {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+    /// seconds left to enter the code
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct TokenSchema {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ProfileSchema {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub email_verified_at: Option<String>,
+    pub totp_enabled_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
 #[derive(Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct UserSchema {
     pub id: i32,
     pub name: String,
     pub email: String,
-    pub token: String,
+    pub email_verified_at: Option<String>,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -0,0 +1,46 @@
+use crate::{core::state, services::jwt_keys};
+use axum::{http::header, response::IntoResponse, Json};
+use axum_macros::debug_handler;
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+// lets verifiers pick up a new key well within `JWT_KEY_PUBLISH_DELAY`
+const JWKS_MAX_AGE: u64 = 60 * 5;
+
+/// Served at the root of the host, next to `/api`
+pub fn route() -> OpenApiRouter<Arc<state::AppState>> {
+    let router = OpenApiRouter::new().routes(routes!(jwks));
+
+    OpenApiRouter::new().nest("/.well-known", router)
+}
+
+/// JSON Web Key Set
+///
+/// Public keys verifying the access tokens, selected by the `kid` of the token header.
+/// Empty when the tokens are signed with a shared secret.
+#[utoipa::path(
+  get,
+  path = "/jwks.json",
+  responses(
+    (status = 200, description = "Key set", body = JwksSchema),
+  ),
+  tag = crate::api_doc::WELL_KNOWN_TAG
+)]
+#[debug_handler]
+async fn jwks() -> impl IntoResponse {
+    (
+        [(
+            header::CACHE_CONTROL,
+            format!("public, max-age={JWKS_MAX_AGE}"),
+        )],
+        Json(jwt_keys::current().jwks().clone()),
+    )
+}
+
+#[derive(Serialize, ToSchema)]
+struct JwksSchema {
+    #[schema(value_type = Vec<Object>)]
+    pub keys: Vec<serde_json::Value>,
+}
@@ -0,0 +1,77 @@
+//! Personal access tokens, sent in the `PRIVATE-TOKEN` header by scripts and CI jobs
+//!
+//! Only the sha256 hash of a token is stored, the plain token is shown once on creation.
+
+use crate::{
+    core::exception::HttpException,
+    http_exception, http_exception_or,
+    utils::{random_token, sha256_hex},
+};
+use axum::http::Method;
+use entity::{
+    personal_access_token,
+    prelude::{PersonalAccessToken, User},
+    soft_delete::SoftDelete,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter, Set,
+};
+
+// lowercase like every `HeaderName`, header names are case insensitive
+pub const HEADER: &str = "private-token";
+pub const TOKEN_PREFIX: &str = "pat_";
+
+/// Allows safe methods only
+pub const SCOPE_READ: &str = "read";
+/// Allows every method
+pub const SCOPE_WRITE: &str = "write";
+pub const SCOPES: [&str; 2] = [SCOPE_READ, SCOPE_WRITE];
+
+/// Generate a new token, returns the plain token and the hash to store
+pub fn generate() -> (String, String) {
+    let token = format!("{TOKEN_PREFIX}{}", random_token(32));
+    let hash = sha256_hex(&token);
+    (token, hash)
+}
+
+/// Look up a token and check it has not expired and its user is not disabled,
+/// its last use is recorded
+pub async fn authenticate<C>(
+    db: &C,
+    token: &str,
+) -> Result<personal_access_token::Model, HttpException>
+where
+    C: ConnectionTrait,
+{
+    let model = http_exception_or!(
+        PersonalAccessToken::find()
+            .filter(personal_access_token::Column::TokenHash.eq(sha256_hex(token)))
+            .one(db)
+            .await?,
+        UnauthorizedException,
+        "Invalid access token"
+    );
+
+    let now = chrono::Utc::now();
+    if model.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        http_exception!(UnauthorizedException, "Access token has expired");
+    }
+    let user = User::find_active_by_id(model.user_id).one(db).await?;
+    if user.is_none_or(|user| user.disabled_at.is_some()) {
+        http_exception!(UnauthorizedException, "Account is disabled");
+    }
+
+    let mut active = model.into_active_model();
+    active.last_used_at = Set(Some(now));
+    Ok(active.update(db).await?)
+}
+
+/// Whether the scopes of a token allow the request method
+pub fn allows(token: &personal_access_token::Model, method: &Method) -> bool {
+    let mut scopes = token.scopes.split_whitespace();
+    if method.is_safe() {
+        scopes.any(|scope| scope == SCOPE_READ || scope == SCOPE_WRITE)
+    } else {
+        scopes.any(|scope| scope == SCOPE_WRITE)
+    }
+}
@@ -0,0 +1,42 @@
+//! Audit trail of administrative actions
+
+use entity::audit_log;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, Set};
+
+pub mod actions {
+    pub const USER_LIST: &str = "user.list";
+    pub const USER_VIEW: &str = "user.view";
+    pub const USER_DELETE: &str = "user.delete";
+    pub const USER_RESTORE: &str = "user.restore";
+    pub const USER_ERASE: &str = "user.erase";
+    pub const USER_DISABLE: &str = "user.disable";
+    pub const USER_ENABLE: &str = "user.enable";
+    pub const USER_LOGOUT: &str = "user.logout";
+    pub const USER_IMPERSONATE: &str = "user.impersonate";
+    pub const TOKEN_REVOKE: &str = "token.revoke";
+    pub const AUDIT_LOG_LIST: &str = "audit_log.list";
+}
+
+/// Record an action performed by `actor_id` on `target_user_id`
+pub async fn record<C>(
+    db: &C,
+    actor_id: i32,
+    action: &str,
+    target_user_id: Option<i32>,
+    details: Option<serde_json::Value>,
+    ip: Option<String>,
+) -> Result<audit_log::Model, DbErr>
+where
+    C: ConnectionTrait,
+{
+    audit_log::ActiveModel {
+        actor_id: Set(Some(actor_id)),
+        action: Set(action.to_string()),
+        target_user_id: Set(target_user_id),
+        details: Set(details),
+        ip: Set(ip),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
@@ -0,0 +1,282 @@
+//! Keys signing the access tokens
+//!
+//! With `JWT_ALGORITHM=HS256` tokens are signed with `JWT_SECRET`. With `RS256` or `EdDSA`
+//! every `{kid}.pem` private key in `JWT_KEYS_DIR` is loaded and its public half published
+//! at `/.well-known/jwks.json`, so other services can verify the tokens. A token carries the
+//! `kid` of the key that signed it and stays valid as long as that key is in the directory.
+//!
+//! To rotate, add a key whose name sorts after the current one, a date works. The directory
+//! is reloaded every `JWT_KEYS_RELOAD_INTERVAL`: the new key is published right away and
+//! signs once its file is older than `JWT_KEY_PUBLISH_DELAY`, so verifiers caching the key
+//! set already know it. Remove the old key once the last tokens it signed have expired.
+
+use crate::core::config;
+use anyhow::anyhow;
+use jsonwebtoken::{
+    jwk::{Jwk, JwkSet, PublicKeyUse},
+    Algorithm, DecodingKey, EncodingKey, Header,
+};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+static KEYS: OnceCell<RwLock<Arc<KeySet>>> = OnceCell::new();
+
+/// The keys loaded at one time, swapped as a whole on reload
+pub struct KeySet {
+    algorithm: Algorithm,
+    /// `None` for the shared secret
+    signing_kid: Option<String>,
+    encoding: EncodingKey,
+    /// keyed by kid, the shared secret has an empty one
+    decoding: HashMap<String, DecodingKey>,
+    jwks: JwkSet,
+}
+
+impl KeySet {
+    fn from_secret(secret: &str) -> Self {
+        Self {
+            algorithm: Algorithm::HS256,
+            signing_kid: None,
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: HashMap::from([(String::new(), DecodingKey::from_secret(secret.as_bytes()))]),
+            jwks: JwkSet { keys: Vec::new() },
+        }
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub fn signing_kid(&self) -> Option<&str> {
+        self.signing_kid.as_deref()
+    }
+
+    /// Key verifying a token signed by `kid`
+    pub fn decoding(&self, kid: Option<&str>) -> Option<&DecodingKey> {
+        self.decoding.get(kid.unwrap_or_default())
+    }
+
+    /// Public keys of the key pairs, empty with a shared secret
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+
+    /// Sign claims with the current signing key
+    pub fn encode<T: Serialize>(&self, claims: &T) -> jsonwebtoken::errors::Result<String> {
+        let mut header = Header::new(self.algorithm);
+        header.kid = self.signing_kid.clone();
+        jsonwebtoken::encode(&header, claims, &self.encoding)
+    }
+}
+
+/// Load the keys, must run before any token is signed or verified
+pub fn init(config: &config::Config) -> anyhow::Result<()> {
+    check_secret(config)?;
+    let keys = load(config)?;
+    if let Some(kid) = keys.signing_kid() {
+        tracing::info!(kid, "signing access tokens");
+    }
+    KEYS.set(RwLock::new(Arc::new(keys)))
+        .map_err(|_| anyhow!("❌ JWT keys are already initialized"))
+}
+
+/// The shared secret everybody can read in the sources must not sign tokens in production
+fn check_secret(config: &config::Config) -> anyhow::Result<()> {
+    if config.is_production()
+        && config.jwt_algorithm() == Algorithm::HS256
+        && config.jwt_secret() == config::DEFAULT_JWT_SECRET
+    {
+        return Err(anyhow!(
+            "❌ JWT_SECRET must be set in production, or JWT_ALGORITHM set to RS256 or EdDSA"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Keys currently in use
+pub fn current() -> Arc<KeySet> {
+    KEYS.get()
+        .expect("JWT keys are not initialized")
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+}
+
+/// Reload the key directory in the background until the runtime shuts down
+pub fn spawn() -> Option<JoinHandle<()>> {
+    let config = config::Config::global();
+    if config.jwt_algorithm() == Algorithm::HS256 {
+        return None;
+    }
+
+    let period = Duration::from_secs(config.jwt_keys_reload_interval().max(1));
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // the first tick completes immediately, the keys were just loaded by `init`
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match tokio::task::spawn_blocking(move || load(config)).await {
+                Ok(Ok(keys)) => replace(keys),
+                Ok(Err(err)) => tracing::error!(%err, "failed to reload the JWT keys"),
+                Err(err) => tracing::error!(%err, "failed to reload the JWT keys"),
+            }
+        }
+    }))
+}
+
+fn replace(keys: KeySet) {
+    let Some(lock) = KEYS.get() else {
+        return;
+    };
+    let mut current = lock.write().unwrap_or_else(|err| err.into_inner());
+    if current.signing_kid != keys.signing_kid {
+        tracing::info!(kid = keys.signing_kid(), "signing access tokens");
+    }
+    *current = Arc::new(keys);
+}
+
+fn load(config: &config::Config) -> anyhow::Result<KeySet> {
+    match config.jwt_algorithm() {
+        Algorithm::HS256 => Ok(KeySet::from_secret(config.jwt_secret())),
+        algorithm => load_dir(
+            algorithm,
+            Path::new(config.jwt_keys_dir()),
+            Duration::from_secs(config.jwt_key_publish_delay()),
+            SystemTime::now(),
+        ),
+    }
+}
+
+fn load_dir(
+    algorithm: Algorithm,
+    dir: &Path,
+    publish_delay: Duration,
+    now: SystemTime,
+) -> anyhow::Result<KeySet> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .map_err(|err| anyhow!("❌ Failed to read the JWT keys in {}: {err}", dir.display()))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().is_none_or(|extension| extension != "pem") {
+            continue;
+        }
+        let Some(kid) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        files.push((kid.to_string(), path.clone(), entry.metadata()?.modified()?));
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // the newest key published for long enough, or the oldest one while none is
+    let signing_kid = files
+        .iter()
+        .rev()
+        .find(|(_, _, modified)| *modified + publish_delay <= now)
+        .or(files.first())
+        .map(|(kid, _, _)| kid.clone())
+        .ok_or_else(|| anyhow!("❌ No JWT key found in {}", dir.display()))?;
+
+    let mut encoding = None;
+    let mut decoding = HashMap::new();
+    let mut jwks = JwkSet { keys: Vec::new() };
+    for (kid, path, _) in files {
+        let pem = std::fs::read(&path)?;
+        let key = match algorithm {
+            Algorithm::RS256 => EncodingKey::from_rsa_pem(&pem),
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(&pem),
+            _ => return Err(anyhow!("❌ Unsupported JWT algorithm {algorithm:?}")),
+        }
+        .map_err(|err| anyhow!("❌ Invalid JWT key {}: {err}", path.display()))?;
+
+        let mut jwk = Jwk::from_encoding_key(&key, algorithm)?;
+        jwk.common.key_id = Some(kid.clone());
+        jwk.common.public_key_use = Some(PublicKeyUse::Signature);
+        decoding.insert(kid.clone(), DecodingKey::from_jwk(&jwk)?);
+        jwks.keys.push(jwk);
+        if kid == signing_kid {
+            encoding = Some(key);
+        }
+    }
+
+    Ok(KeySet {
+        algorithm,
+        encoding: encoding.expect("the signing key is one of the loaded keys"),
+        signing_kid: Some(signing_kid),
+        decoding,
+        jwks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::Validation;
+    use serde::Deserialize;
+
+    const PEM: &str = include_str!("testdata/oidc_rsa.pem");
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TestClaims {
+        sub: String,
+        exp: u64,
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("2026-01-01.pem"), PEM).unwrap();
+        std::fs::write(dir.path().join("2026-02-01.pem"), PEM).unwrap();
+        std::fs::write(dir.path().join("README"), "not a key").unwrap();
+        let delay = Duration::from_secs(60 * 60);
+
+        // both keys are published, the newest one waits before signing
+        let keys = load_dir(Algorithm::RS256, dir.path(), delay, SystemTime::now()).unwrap();
+        assert_eq!(keys.signing_kid(), Some("2026-01-01"));
+        assert_eq!(keys.jwks().keys.len(), 2);
+        assert!(keys.jwks().find("2026-02-01").is_some());
+
+        let later = SystemTime::now() + delay;
+        let keys = load_dir(Algorithm::RS256, dir.path(), delay, later).unwrap();
+        assert_eq!(keys.signing_kid(), Some("2026-02-01"));
+
+        let claims = TestClaims {
+            sub: "1".to_string(),
+            exp: 4_102_444_800,
+        };
+        let token = keys.encode(&claims).unwrap();
+        let header = jsonwebtoken::decode_header(&token).unwrap();
+        assert_eq!(header.kid.as_deref(), Some("2026-02-01"));
+        let decoding = keys.decoding(header.kid.as_deref()).unwrap();
+        let decoded = jsonwebtoken::decode::<TestClaims>(
+            &token,
+            decoding,
+            &Validation::new(Algorithm::RS256),
+        )
+        .unwrap();
+        assert_eq!(decoded.claims, claims);
+    }
+
+    #[test]
+    fn test_empty_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = load_dir(
+            Algorithm::RS256,
+            dir.path(),
+            Duration::ZERO,
+            SystemTime::now(),
+        );
+        assert!(result.is_err());
+    }
+}
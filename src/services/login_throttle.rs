@@ -0,0 +1,162 @@
+//! Brute-force protection for logins
+//!
+//! Failed attempts are counted per email and per client ip. Once a counter reaches its limit
+//! within `LOGIN_ATTEMPT_WINDOW`, the email or ip is locked out. Every lockout within a day
+//! lasts twice as long as the previous one, up to `LOGIN_LOCKOUT_MAX`.
+
+use crate::core::{
+    config,
+    exception::{HttpException, RetryAfter},
+    state::RedisPool,
+};
+use axum::response::{IntoResponse, Response};
+use bb8_redis::redis::{self, AsyncCommands};
+
+// how long previous lockouts are remembered for the backoff, in seconds
+const LOCKOUT_MEMORY: i64 = 60 * 60 * 24;
+
+#[derive(Clone, Copy, Debug)]
+enum Subject<'a> {
+    Email(&'a str),
+    Ip(&'a str),
+}
+
+impl Subject<'_> {
+    fn key(&self, kind: &str) -> String {
+        let config = config::Config::global();
+        match self {
+            Self::Email(email) => format!(
+                "{}:login_{kind}:email:{}",
+                config.app_auth_key(),
+                email.to_lowercase()
+            ),
+            Self::Ip(ip) => format!("{}:login_{kind}:ip:{ip}", config.app_auth_key()),
+        }
+    }
+
+    fn max_attempts(&self) -> u32 {
+        let config = config::Config::global();
+        match self {
+            Self::Email(_) => config.login_max_attempts(),
+            Self::Ip(_) => config.login_ip_max_attempts(),
+        }
+    }
+}
+
+fn subjects<'a>(email: &'a str, ip: Option<&'a str>) -> Vec<Subject<'a>> {
+    let mut subjects = vec![Subject::Email(email)];
+    if let Some(ip) = ip {
+        subjects.push(Subject::Ip(ip));
+    }
+    subjects
+}
+
+/// Seconds of the `n`th lockout
+fn lockout_duration(lockouts: u32) -> i64 {
+    let config = config::Config::global();
+    let factor = 1i64 << lockouts.saturating_sub(1).min(30);
+    config
+        .login_lockout()
+        .saturating_mul(factor)
+        .min(config.login_lockout_max())
+}
+
+/// Seconds until neither the email nor the ip is locked out, `None` when they are not
+pub async fn locked_for(
+    pool: &RedisPool,
+    email: &str,
+    ip: Option<&str>,
+) -> Result<Option<u64>, HttpException> {
+    let mut conn = pool.get().await?;
+    let mut locked_for = None;
+    for subject in subjects(email, ip) {
+        let ttl: i64 = conn.ttl(subject.key("lock")).await?;
+        if ttl > 0 {
+            locked_for = locked_for.max(Some(ttl as u64));
+        }
+    }
+
+    Ok(locked_for)
+}
+
+/// 429 answered to a locked out login, with a `Retry-After` header
+pub fn locked_out(seconds: u64) -> Response {
+    (
+        RetryAfter(seconds),
+        HttpException::TooManyRequestsException(Some(format!(
+            "Too many failed login attempts, try again in {seconds} seconds"
+        ))),
+    )
+        .into_response()
+}
+
+/// Count a failed login, locking the email or the ip out once its limit is reached
+pub async fn record_failure(
+    pool: &RedisPool,
+    email: &str,
+    ip: Option<&str>,
+) -> Result<(), HttpException> {
+    let window = config::Config::global().login_attempt_window();
+    let mut conn = pool.get().await?;
+    for subject in subjects(email, ip) {
+        let (failures,): (u32,) = redis::pipe()
+            .atomic()
+            .incr(subject.key("failures"), 1)
+            .expire(subject.key("failures"), window)
+            .ignore()
+            .query_async(&mut *conn)
+            .await?;
+        if failures < subject.max_attempts() {
+            continue;
+        }
+
+        let (lockouts,): (u32,) = redis::pipe()
+            .atomic()
+            .incr(subject.key("lockouts"), 1)
+            .expire(subject.key("lockouts"), LOCKOUT_MEMORY)
+            .ignore()
+            .query_async(&mut *conn)
+            .await?;
+        let duration = lockout_duration(lockouts);
+        tracing::warn!(?subject, lockouts, duration, "login locked out");
+
+        let _: () = redis::pipe()
+            .atomic()
+            .set_ex(subject.key("lock"), 1, duration as u64)
+            .ignore()
+            .del(subject.key("failures"))
+            .ignore()
+            .query_async(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Reset the counters of an email after a successful login, the ip keeps its count
+pub async fn record_success(pool: &RedisPool, email: &str) -> Result<(), HttpException> {
+    let subject = Subject::Email(email);
+    let _: () = pool
+        .get()
+        .await?
+        .del(&[subject.key("failures"), subject.key("lockouts")])
+        .await?;
+
+    Ok(())
+}
+
+/// Drop every counter kept for an email, used when the account is erased
+pub async fn forget(pool: &RedisPool, email: &str) -> Result<(), HttpException> {
+    let subject = Subject::Email(email);
+    let _: () = pool
+        .get()
+        .await?
+        .del(&[
+            subject.key("failures"),
+            subject.key("lockouts"),
+            subject.key("lock"),
+        ])
+        .await?;
+
+    Ok(())
+}
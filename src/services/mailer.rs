@@ -0,0 +1,143 @@
+//! Outgoing mail
+//!
+//! `SmtpMailer` delivers through an SMTP relay, `FileMailer` writes every mail as a json file
+//! into an outbox directory so tests and local development can read them without a mail server.
+
+use crate::core::config::{self, MailerKind};
+use anyhow::Result;
+use lettre::{
+    message::header::ContentType, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Arc};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<()>;
+}
+
+/// Build the mailer selected by `MAILER`
+pub fn from_config(config: &config::Config) -> Result<Arc<dyn Mailer>> {
+    let mailer: Arc<dyn Mailer> = match config.mailer() {
+        MailerKind::Smtp => Arc::new(SmtpMailer::new(config)?),
+        MailerKind::File => Arc::new(FileMailer::new(config.mail_outbox_dir())),
+    };
+
+    Ok(mailer)
+}
+
+pub struct SmtpMailer {
+    from: String,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &config::Config) -> Result<Self> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(config.smtp_host())?
+            .port(config.smtp_port());
+        if let Some((username, password)) = config.smtp_credentials() {
+            builder =
+                builder.credentials(Credentials::new(username.to_string(), password.to_string()));
+        }
+
+        Ok(Self {
+            from: config.mail_from().to_string(),
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.parse()?)
+            .to(mail.to.parse()?)
+            .subject(mail.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(mail.body)?;
+        self.transport.send(message).await?;
+
+        Ok(())
+    }
+}
+
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Every mail in the outbox, oldest first
+    #[cfg(test)]
+    pub async fn read_all(&self) -> Result<Vec<Mail>> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut paths = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            paths.push(entry.path());
+        }
+        paths.sort();
+
+        let mut mails = Vec::with_capacity(paths.len());
+        for path in paths {
+            let content = tokio::fs::read(path).await?;
+            mails.push(serde_json::from_slice(&content)?);
+        }
+
+        Ok(mails)
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        // prefix with the time so the outbox lists in sending order
+        let name = format!(
+            "{}-{}.json",
+            time::OffsetDateTime::now_utc().unix_timestamp_nanos(),
+            Uuid::new_v4()
+        );
+        tokio::fs::write(self.dir.join(name), serde_json::to_vec_pretty(&mail)?).await?;
+
+        tracing::info!(to = %mail.to, subject = %mail.subject, "mail written to outbox");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_mailer_outbox() {
+        let dir = tempfile::tempdir().unwrap();
+        let mailer = FileMailer::new(dir.path());
+        let mail = Mail {
+            to: "user@domain.net".to_string(),
+            subject: "Hello".to_string(),
+            body: "World".to_string(),
+        };
+
+        mailer.send(mail.clone()).await.unwrap();
+
+        assert_eq!(mailer.read_all().await.unwrap(), vec![mail]);
+    }
+}
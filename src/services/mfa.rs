@@ -0,0 +1,184 @@
+//! TOTP two-factor authentication
+//!
+//! The secret is stored on the user when enrolling and only becomes active once a valid code
+//! was entered. Every accepted code is remembered for a short while so it cannot be replayed,
+//! recovery codes are stored hashed and can each be used once.
+
+use crate::{
+    core::{config, exception::HttpException, state::RedisPool},
+    utils::{random_token, sha256_hex},
+};
+use bb8_redis::redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
+use entity::{mfa_recovery_code, prelude::MfaRecoveryCode, user};
+use sea_orm::{sea_query::Expr, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const RECOVERY_CODE_COUNT: usize = 10;
+// codes of the previous and next time step are accepted too
+const SKEW: u8 = 1;
+const STEP: u64 = 30;
+
+fn used_code_key(user_id: i32, code: &str) -> String {
+    format!(
+        "{}:mfa_used:{user_id}:{code}",
+        config::Config::global().app_auth_key()
+    )
+}
+
+/// A new random base32 encoded secret
+pub fn new_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+fn totp(secret: &str, account: &str) -> Result<TOTP, HttpException> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|err| HttpException::InternalServerErrorException(Some(err.to_string())))?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        SKEW,
+        STEP,
+        secret,
+        Some(config::Config::global().mfa_issuer().to_string()),
+        account.to_string(),
+    )
+    .map_err(|err| HttpException::InternalServerErrorException(Some(err.to_string())))
+}
+
+/// `otpauth://` uri to be shown as a QR code to authenticator apps
+pub fn otpauth_uri(secret: &str, account: &str) -> Result<String, HttpException> {
+    Ok(totp(secret, account)?.get_url())
+}
+
+/// Check a TOTP code against the user's secret, each code is only accepted once
+pub async fn check_totp(
+    pool: &RedisPool,
+    user: &user::Model,
+    code: &str,
+) -> Result<bool, HttpException> {
+    let Some(secret) = user.totp_secret.as_deref() else {
+        return Ok(false);
+    };
+    let valid = totp(secret, &user.email)?
+        .check_current(code)
+        .map_err(|err| HttpException::InternalServerErrorException(Some(err.to_string())))?;
+    if !valid {
+        return Ok(false);
+    }
+
+    // the code stays valid for the whole skew window
+    let options = SetOptions::default()
+        .conditional_set(ExistenceCheck::NX)
+        .with_expiration(SetExpiry::EX(STEP * (2 * SKEW as u64 + 1)));
+    let fresh: Option<String> = pool
+        .get()
+        .await?
+        .set_options(used_code_key(user.id, code), 1, options)
+        .await?;
+
+    Ok(fresh.is_some())
+}
+
+/// Check the second factor of a user with MFA enabled, either a TOTP code or a recovery code
+pub async fn verify<C>(
+    db: &C,
+    pool: &RedisPool,
+    user: &user::Model,
+    code: &str,
+) -> Result<bool, HttpException>
+where
+    C: ConnectionTrait,
+{
+    if user.totp_enabled_at.is_none() {
+        return Ok(false);
+    }
+
+    let code = code.trim();
+    if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
+        check_totp(pool, user, code).await
+    } else {
+        use_recovery_code(db, user.id, code).await
+    }
+}
+
+/// Replace the recovery codes of a user, returns the new plain codes
+pub async fn replace_recovery_codes<C>(db: &C, user_id: i32) -> Result<Vec<String>, HttpException>
+where
+    C: ConnectionTrait,
+{
+    MfaRecoveryCode::delete_many()
+        .filter(mfa_recovery_code::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let token = random_token(5);
+            format!("{}-{}", &token[..5], &token[5..])
+        })
+        .collect();
+    let models = codes.iter().map(|code| mfa_recovery_code::ActiveModel {
+        code_hash: Set(hash_recovery_code(code)),
+        user_id: Set(user_id),
+        ..Default::default()
+    });
+    MfaRecoveryCode::insert_many(models).exec(db).await?;
+
+    Ok(codes)
+}
+
+pub async fn delete_recovery_codes<C>(db: &C, user_id: i32) -> Result<(), HttpException>
+where
+    C: ConnectionTrait,
+{
+    MfaRecoveryCode::delete_many()
+        .filter(mfa_recovery_code::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+async fn use_recovery_code<C>(db: &C, user_id: i32, code: &str) -> Result<bool, HttpException>
+where
+    C: ConnectionTrait,
+{
+    // a single update so two requests racing with the same code can not both spend it
+    let used = MfaRecoveryCode::update_many()
+        .col_expr(
+            mfa_recovery_code::Column::UsedAt,
+            Expr::value(chrono::Utc::now()),
+        )
+        .filter(mfa_recovery_code::Column::UserId.eq(user_id))
+        .filter(mfa_recovery_code::Column::CodeHash.eq(hash_recovery_code(code)))
+        .filter(mfa_recovery_code::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(used.rows_affected == 1)
+}
+
+// recovery codes are typed by hand, ignore case and separators
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    sha256_hex(&normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovery_code_hash_ignores_format() {
+        assert_eq!(
+            hash_recovery_code("ab12c-3d4ef"),
+            hash_recovery_code(" AB12C3D4EF ")
+        );
+    }
+}
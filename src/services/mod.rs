@@ -0,0 +1,18 @@
+pub mod api_token;
+pub mod audit;
+pub mod jwt_keys;
+pub mod login_throttle;
+pub mod mailer;
+pub mod mfa;
+pub mod oidc;
+pub mod one_time_token;
+pub mod password;
+pub mod personal_data;
+pub mod publisher;
+pub mod purge;
+pub mod rbac;
+pub mod reaction;
+pub mod session;
+pub mod tag;
+pub mod token_denylist;
+pub mod upload;
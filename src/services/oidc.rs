@@ -0,0 +1,663 @@
+//! OpenID Connect login with the authorization code flow and PKCE
+//!
+//! The login redirect stores the state, nonce and PKCE verifier in redis until the provider
+//! calls back. The pending login is bound to the browser that started it by a random value
+//! kept in a cookie, only its hash is stored. The code is then exchanged for an ID token, which is checked against the keys
+//! published by the provider. Provider metadata and keys are cached in memory, the keys are
+//! fetched again when a token is signed with an unknown `kid`.
+
+use crate::{
+    core::{
+        config::{self, OidcProvider},
+        exception::HttpException,
+        state::RedisPool,
+    },
+    http_exception, http_exception_or,
+    services::rbac,
+    utils::{random_token, sha256_hex},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bb8_redis::redis::AsyncCommands;
+use entity::{
+    prelude::{User, UserIdentity},
+    soft_delete::SoftDelete,
+    user, user_identity,
+};
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use once_cell::sync::Lazy;
+use reqwest::Url;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fmt::Display, time::Duration};
+use tokio::sync::RwLock;
+
+// time the user has to log in at the provider
+pub const PENDING_LOGIN_TTL: u64 = 60 * 10;
+// clock skew tolerated on the ID token timestamps, in seconds
+const LEEWAY: u64 = 60;
+const ALGORITHMS: [Algorithm; 7] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+static HTTP: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("failed to build the OpenID Connect http client")
+});
+// keyed by issuer
+static METADATA: Lazy<RwLock<HashMap<String, ProviderMetadata>>> = Lazy::new(Default::default);
+// keyed by jwks uri
+static KEYS: Lazy<RwLock<HashMap<String, JwkSet>>> = Lazy::new(Default::default);
+
+/// The part of the discovery document used by the login flow
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// A login waiting for the provider to call back
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PendingLogin {
+    pub provider: String,
+    pub state: String,
+    pub nonce: String,
+    pub verifier: String,
+    /// hash of the binding cookie of the browser that started the login
+    pub binding_hash: String,
+    /// path of the frontend to send the user to afterwards
+    pub redirect: Option<String>,
+    /// set when a logged in user links an identity instead of logging in
+    pub link_user_id: Option<i32>,
+}
+
+impl PendingLogin {
+    pub fn new(
+        provider: &str,
+        binding: &str,
+        redirect: Option<String>,
+        link_user_id: Option<i32>,
+    ) -> Self {
+        Self {
+            provider: provider.to_string(),
+            state: random_token(16),
+            nonce: random_token(16),
+            verifier: random_token(32),
+            binding_hash: sha256_hex(binding),
+            redirect,
+            link_user_id,
+        }
+    }
+
+    /// Make sure the callback comes from the browser that started the login, otherwise a
+    /// callback url of someone else's login could be opened to log in to their account
+    pub fn verify_binding(&self, binding: Option<&str>) -> Result<(), HttpException> {
+        if binding.map(sha256_hex).as_deref() != Some(self.binding_hash.as_str()) {
+            http_exception!(
+                BadRequestException,
+                "The login was started in another browser"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Claims of an ID token the application relies on
+#[derive(Clone, Debug, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+fn provider_error(err: impl Display) -> HttpException {
+    HttpException::BadGatewayException(Some(format!("Identity provider error: {err}")))
+}
+
+async fn fetch_json<T: DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+) -> Result<T, HttpException> {
+    request
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(provider_error)?
+        .json()
+        .await
+        .map_err(provider_error)
+}
+
+/// `S256` PKCE challenge of a verifier
+pub fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Metadata from the discovery document of the issuer
+pub async fn discover(issuer: &str) -> Result<ProviderMetadata, HttpException> {
+    let issuer = issuer.trim_end_matches('/');
+    if let Some(metadata) = METADATA.read().await.get(issuer) {
+        return Ok(metadata.clone());
+    }
+
+    let url = format!("{issuer}/.well-known/openid-configuration");
+    let metadata: ProviderMetadata = fetch_json(HTTP.get(url)).await?;
+    if metadata.issuer.trim_end_matches('/') != issuer {
+        return Err(provider_error(format!(
+            "discovery document of {issuer} is for {}",
+            metadata.issuer
+        )));
+    }
+    METADATA
+        .write()
+        .await
+        .insert(issuer.to_string(), metadata.clone());
+
+    Ok(metadata)
+}
+
+/// Url of the provider's login page for a pending login
+pub async fn authorization_url(
+    provider: &OidcProvider,
+    redirect_uri: &str,
+    pending: &PendingLogin,
+) -> Result<String, HttpException> {
+    let metadata = discover(&provider.issuer).await?;
+    let url = Url::parse_with_params(
+        &metadata.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", provider.client_id.as_str()),
+            ("redirect_uri", redirect_uri),
+            ("scope", provider.scopes.as_str()),
+            ("state", pending.state.as_str()),
+            ("nonce", pending.nonce.as_str()),
+            ("code_challenge", code_challenge(&pending.verifier).as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map_err(provider_error)?;
+
+    Ok(url.into())
+}
+
+/// Exchange the code of the callback for the verified claims of the ID token
+pub async fn exchange_code(
+    provider: &OidcProvider,
+    redirect_uri: &str,
+    code: &str,
+    pending: &PendingLogin,
+) -> Result<IdTokenClaims, HttpException> {
+    let metadata = discover(&provider.issuer).await?;
+    let response: TokenResponse = fetch_json(HTTP.post(&metadata.token_endpoint).form(&[
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", redirect_uri),
+        ("client_id", provider.client_id.as_str()),
+        ("client_secret", provider.client_secret.as_str()),
+        ("code_verifier", pending.verifier.as_str()),
+    ]))
+    .await?;
+
+    verify_id_token(provider, &metadata, &response.id_token, &pending.nonce).await
+}
+
+async fn verify_id_token(
+    provider: &OidcProvider,
+    metadata: &ProviderMetadata,
+    id_token: &str,
+    nonce: &str,
+) -> Result<IdTokenClaims, HttpException> {
+    let invalid = |err: &dyn Display| {
+        HttpException::UnauthorizedException(Some(format!("Invalid ID token: {err}")))
+    };
+
+    let header = jsonwebtoken::decode_header(id_token).map_err(|err| invalid(&err))?;
+    if !ALGORITHMS.contains(&header.alg) {
+        return Err(invalid(&format!(
+            "algorithm {:?} is not allowed",
+            header.alg
+        )));
+    }
+    let key = decoding_key(&metadata.jwks_uri, header.kid.as_deref()).await?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.leeway = LEEWAY;
+    validation.set_issuer(&[&metadata.issuer]);
+    validation.set_audience(&[&provider.client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+    let claims = jsonwebtoken::decode::<IdTokenClaims>(id_token, &key, &validation)
+        .map_err(|err| invalid(&err))?
+        .claims;
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(invalid(&"nonce mismatch"));
+    }
+
+    Ok(claims)
+}
+
+async fn decoding_key(jwks_uri: &str, kid: Option<&str>) -> Result<DecodingKey, HttpException> {
+    let find = |keys: &JwkSet| match kid {
+        Some(kid) => keys.find(kid).cloned(),
+        // providers with a single key may leave out the `kid`
+        None if keys.keys.len() == 1 => keys.keys.first().cloned(),
+        None => None,
+    };
+
+    let cached = KEYS.read().await.get(jwks_uri).and_then(find);
+    let jwk = match cached {
+        Some(jwk) => jwk,
+        None => {
+            // unknown key, the provider may have rotated its keys
+            let keys: JwkSet = fetch_json(HTTP.get(jwks_uri)).await?;
+            let jwk = find(&keys);
+            KEYS.write().await.insert(jwks_uri.to_string(), keys);
+            jwk.ok_or_else(|| {
+                HttpException::UnauthorizedException(Some(
+                    "Invalid ID token: unknown signing key".to_string(),
+                ))
+            })?
+        }
+    };
+
+    DecodingKey::from_jwk(&jwk).map_err(provider_error)
+}
+
+fn pending_key(state: &str) -> String {
+    format!(
+        "{}:oidc_state:{}",
+        config::Config::global().app_auth_key(),
+        sha256_hex(state)
+    )
+}
+
+pub async fn save_pending(pool: &RedisPool, pending: &PendingLogin) -> Result<(), HttpException> {
+    let value = serde_json::to_string(pending)
+        .map_err(|err| HttpException::InternalServerErrorException(Some(err.to_string())))?;
+    let _: () = pool
+        .get()
+        .await?
+        .set_ex(pending_key(&pending.state), value, PENDING_LOGIN_TTL)
+        .await?;
+
+    Ok(())
+}
+
+/// Use up the pending login of a callback state
+pub async fn take_pending(
+    pool: &RedisPool,
+    state: &str,
+) -> Result<Option<PendingLogin>, HttpException> {
+    let value: Option<String> = pool.get().await?.get_del(pending_key(state)).await?;
+
+    Ok(value.and_then(|value| serde_json::from_str(&value).ok()))
+}
+
+/// The user an identity is linked to. Unknown identities are linked to the user with the
+/// same email when the provider verified it, otherwise a new user is created.
+pub async fn resolve_user<C>(
+    db: &C,
+    provider: &str,
+    claims: &IdTokenClaims,
+) -> Result<user::Model, HttpException>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if let Some(identity) = find_identity(db, provider, &claims.sub).await? {
+        // the identity goes with its user, only a soft-deleted one is not found
+        let user = http_exception_or!(
+            User::find_active_by_id(identity.user_id).one(db).await?,
+            ForbiddenException,
+            "Account is disabled"
+        );
+        return Ok(user);
+    }
+
+    let Some(email) = claims.email.as_deref() else {
+        http_exception!(
+            BadRequestException,
+            "The identity provider did not share an email address"
+        );
+    };
+    let existing = User::find_active()
+        .filter(user::Column::Email.eq(email))
+        .one(db)
+        .await?;
+
+    let txn = db.begin().await?;
+    let user = match existing {
+        Some(user) if claims.email_verified => user,
+        Some(_) => {
+            http_exception!(
+                ConflictException,
+                "An account with this email already exists, log in and link the identity from your profile"
+            );
+        }
+        None => {
+            let user = user::ActiveModel {
+                name: Set(available_name(&txn, claims, email).await?),
+                email: Set(email.to_string()),
+                // hashed by `before_save`, a password can be set through the reset flow
+                password: Set(random_token(32)),
+                email_verified_at: Set(claims.email_verified.then(chrono::Utc::now)),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            rbac::assign_role(&txn, user.id, rbac::ROLE_USER).await?;
+            user
+        }
+    };
+    insert_identity(&txn, provider, claims, user.id).await?;
+    txn.commit().await?;
+
+    Ok(user)
+}
+
+/// Link an identity to a user, fails when it is linked to someone else
+pub async fn link<C>(
+    db: &C,
+    provider: &str,
+    claims: &IdTokenClaims,
+    user_id: i32,
+) -> Result<user_identity::Model, HttpException>
+where
+    C: ConnectionTrait,
+{
+    match find_identity(db, provider, &claims.sub).await? {
+        Some(identity) if identity.user_id == user_id => Ok(identity),
+        Some(_) => {
+            http_exception!(
+                ConflictException,
+                "This identity is already linked to another account"
+            );
+        }
+        None => insert_identity(db, provider, claims, user_id).await,
+    }
+}
+
+async fn find_identity<C>(
+    db: &C,
+    provider: &str,
+    subject: &str,
+) -> Result<Option<user_identity::Model>, HttpException>
+where
+    C: ConnectionTrait,
+{
+    Ok(UserIdentity::find()
+        .filter(user_identity::Column::Provider.eq(provider))
+        .filter(user_identity::Column::Subject.eq(subject))
+        .one(db)
+        .await?)
+}
+
+async fn insert_identity<C>(
+    db: &C,
+    provider: &str,
+    claims: &IdTokenClaims,
+    user_id: i32,
+) -> Result<user_identity::Model, HttpException>
+where
+    C: ConnectionTrait,
+{
+    Ok(user_identity::ActiveModel {
+        provider: Set(provider.to_string()),
+        subject: Set(claims.sub.clone()),
+        email: Set(claims.email.clone()),
+        user_id: Set(user_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?)
+}
+
+/// A unique user name derived from the claims
+async fn available_name<C>(
+    db: &C,
+    claims: &IdTokenClaims,
+    email: &str,
+) -> Result<String, HttpException>
+where
+    C: ConnectionTrait,
+{
+    let base = base_name(claims, email);
+    let mut name = base.clone();
+    // soft-deleted users keep their name until they are purged
+    while User::find_with_deleted()
+        .filter(user::Column::Name.eq(&name))
+        .one(db)
+        .await?
+        .is_some()
+    {
+        name = format!("{base}-{}", random_token(3));
+    }
+
+    Ok(name)
+}
+
+fn base_name(claims: &IdTokenClaims, email: &str) -> String {
+    let candidate = claims
+        .preferred_username
+        .as_deref()
+        .or(claims.name.as_deref())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or_default());
+    let name: String = candidate
+        .trim()
+        .chars()
+        .map(|c| if c.is_whitespace() { '-' } else { c })
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+
+    if name.is_empty() {
+        "user".to_string()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! The login flow against a mock provider serving discovery, keys and the token endpoint
+
+    use super::*;
+    use axum::{
+        extract::{Form, State},
+        http::StatusCode,
+        routing::{get, post},
+        Json, Router,
+    };
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    const PRIVATE_KEY: &str = include_str!("testdata/oidc_rsa.pem");
+    const MODULUS: &str = "ldDGmIg9L1f5ocP_VAn1QL7SQ5hlr72cSC5so7gEbdD18HNJB96d_Y6fRow2Ol55SPU-lzAHvFlhgT7I5Ip5_L74tWSfaL_hsKLUUH_DEF6isYIKvDkPenCzDdVh34LQVO-A7AXQqOeHq3QVIj3MQqG1ny9vdR_LyVkVw2PFSCR6HOjKAtP8JoKzLIoyi37Ibijrvufat9cGPyx2w_2wZGnd2lTJaqijy6_-YkHfTDb9rKTekrUGjc4Zh81oiIU1X5C7ZKZkWeU4rtMEldIBiKDlWxP9wIkaHwXy4klFV1d-sF0-ZOPK_6M6q9hUpPnMHMBW6A3z7pQGBG6-KXgg2w";
+    const CLIENT_ID: &str = "client";
+    const REDIRECT_URI: &str = "http://127.0.0.1/callback";
+    const BINDING: &str = "binding";
+
+    #[derive(Default)]
+    struct MockState {
+        issuer: String,
+        /// challenge and nonce of the last authorization request
+        challenge: String,
+        nonce: String,
+    }
+
+    type Mock = Arc<Mutex<MockState>>;
+
+    async fn discovery(State(mock): State<Mock>) -> Json<Value> {
+        let issuer = mock.lock().unwrap().issuer.clone();
+        Json(json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{issuer}/authorize"),
+            "token_endpoint": format!("{issuer}/token"),
+            "jwks_uri": format!("{issuer}/jwks"),
+        }))
+    }
+
+    async fn jwks() -> Json<Value> {
+        Json(json!({
+            "keys": [{ "kty": "RSA", "kid": "test", "alg": "RS256", "use": "sig", "n": MODULUS, "e": "AQAB" }]
+        }))
+    }
+
+    async fn token(
+        State(mock): State<Mock>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Result<Json<Value>, StatusCode> {
+        let mock = mock.lock().unwrap();
+        if form.get("code").map(String::as_str) != Some("code")
+            || form.get("code_verifier").map(|v| code_challenge(v)) != Some(mock.challenge.clone())
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some("test".to_string());
+        let now = chrono::Utc::now().timestamp();
+        let claims = json!({
+            "iss": mock.issuer,
+            "aud": CLIENT_ID,
+            "sub": "alice-id",
+            "exp": now + 300,
+            "iat": now,
+            "nonce": mock.nonce,
+            "email": "alice@example.com",
+            "email_verified": true,
+            "preferred_username": "alice",
+        });
+        let key = EncodingKey::from_rsa_pem(PRIVATE_KEY.as_bytes()).unwrap();
+        let id_token = jsonwebtoken::encode(&header, &claims, &key).unwrap();
+
+        Ok(Json(
+            json!({ "access_token": "access", "token_type": "Bearer", "id_token": id_token }),
+        ))
+    }
+
+    async fn start_provider() -> (OidcProvider, Mock) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let mock = Mock::default();
+        mock.lock().unwrap().issuer = issuer.clone();
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(mock.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let provider = OidcProvider {
+            name: "mock".to_string(),
+            issuer,
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "secret".to_string(),
+            scopes: "openid email profile".to_string(),
+        };
+        (provider, mock)
+    }
+
+    /// Follow the authorization url like the provider would and remember the request
+    async fn authorize(provider: &OidcProvider, mock: &Mock, pending: &PendingLogin) {
+        let url = authorization_url(provider, REDIRECT_URI, pending)
+            .await
+            .unwrap();
+        let url = Url::parse(&url).unwrap();
+        let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["client_id"], CLIENT_ID);
+        assert_eq!(params["state"], pending.state);
+        assert_eq!(params["code_challenge_method"], "S256");
+
+        let mut mock = mock.lock().unwrap();
+        mock.challenge = params["code_challenge"].clone();
+        mock.nonce = params["nonce"].clone();
+    }
+
+    #[tokio::test]
+    async fn test_login_flow() {
+        let (provider, mock) = start_provider().await;
+        let pending = PendingLogin::new(&provider.name, BINDING, None, None);
+        authorize(&provider, &mock, &pending).await;
+        assert!(pending.verify_binding(Some(BINDING)).is_ok());
+
+        let claims = exchange_code(&provider, REDIRECT_URI, "code", &pending)
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, "alice-id");
+        assert_eq!(claims.email.as_deref(), Some("alice@example.com"));
+        assert!(claims.email_verified);
+        assert_eq!(base_name(&claims, "alice@example.com"), "alice");
+    }
+
+    #[tokio::test]
+    async fn test_login_rejects_wrong_verifier() {
+        let (provider, mock) = start_provider().await;
+        let pending = PendingLogin::new(&provider.name, BINDING, None, None);
+        authorize(&provider, &mock, &pending).await;
+
+        let forged = PendingLogin {
+            verifier: random_token(32),
+            ..PendingLogin::new(&provider.name, BINDING, None, None)
+        };
+        let result = exchange_code(&provider, REDIRECT_URI, "code", &forged).await;
+        assert!(matches!(result, Err(HttpException::BadGatewayException(_))));
+    }
+
+    #[tokio::test]
+    async fn test_login_rejects_wrong_nonce() {
+        let (provider, mock) = start_provider().await;
+        let pending = PendingLogin::new(&provider.name, BINDING, None, None);
+        authorize(&provider, &mock, &pending).await;
+
+        let replayed = PendingLogin {
+            nonce: random_token(16),
+            ..pending
+        };
+        let result = exchange_code(&provider, REDIRECT_URI, "code", &replayed).await;
+        assert!(matches!(
+            result,
+            Err(HttpException::UnauthorizedException(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_callback_rejects_other_browser() {
+        let (provider, mock) = start_provider().await;
+        let pending = PendingLogin::new(&provider.name, BINDING, None, Some(1));
+        authorize(&provider, &mock, &pending).await;
+
+        // the callback url was opened without the cookie set by the login redirect
+        for binding in [None, Some("forged")] {
+            assert!(matches!(
+                pending.verify_binding(binding),
+                Err(HttpException::BadRequestException(_))
+            ));
+        }
+        let stored: PendingLogin =
+            serde_json::from_str(&serde_json::to_string(&pending).unwrap()).unwrap();
+        assert_ne!(stored.binding_hash, BINDING);
+        assert!(stored.verify_binding(Some(BINDING)).is_ok());
+    }
+}
@@ -0,0 +1,131 @@
+//! Single use tokens sent by mail
+//!
+//! Only the sha256 of a token is kept in redis, it expires after the ttl of its purpose and is
+//! deleted when used. Issuing a new token invalidates the previous one of the same user and purpose.
+
+use crate::{
+    core::{config, exception::HttpException, state::RedisPool},
+    utils::{random_token, sha256_hex},
+};
+use bb8_redis::redis::{self, AsyncCommands};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Purpose {
+    PasswordReset,
+    EmailVerification,
+    /// password accepted, waiting for the second factor
+    MfaPending,
+}
+
+impl Purpose {
+    pub const ALL: [Self; 3] = [
+        Self::PasswordReset,
+        Self::EmailVerification,
+        Self::MfaPending,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::PasswordReset => "password_reset",
+            Self::EmailVerification => "email_verification",
+            Self::MfaPending => "mfa_pending",
+        }
+    }
+
+    /// Lifetime of the token in seconds
+    pub fn ttl(&self) -> i64 {
+        let config = config::Config::global();
+        match self {
+            Self::PasswordReset => config.password_reset_ttl(),
+            Self::EmailVerification => config.email_verification_ttl(),
+            Self::MfaPending => config.mfa_pending_ttl(),
+        }
+    }
+}
+
+fn token_key(purpose: Purpose, hash: &str) -> String {
+    format!(
+        "{}:{}:{hash}",
+        config::Config::global().app_auth_key(),
+        purpose.name()
+    )
+}
+
+fn user_key(purpose: Purpose, user_id: i32) -> String {
+    format!(
+        "{}:{}_user:{user_id}",
+        config::Config::global().app_auth_key(),
+        purpose.name()
+    )
+}
+
+/// Issue a token for the user, returns the plain token to be mailed
+pub async fn issue(
+    pool: &RedisPool,
+    purpose: Purpose,
+    user_id: i32,
+) -> Result<String, HttpException> {
+    let ttl = purpose.ttl() as u64;
+    let token = random_token(32);
+    let hash = sha256_hex(&token);
+
+    let mut conn = pool.get().await?;
+    let previous: Option<String> = conn.get(user_key(purpose, user_id)).await?;
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    if let Some(previous) = previous {
+        pipe.del(token_key(purpose, &previous)).ignore();
+    }
+    let _: () = pipe
+        .set_ex(token_key(purpose, &hash), user_id, ttl)
+        .ignore()
+        .set_ex(user_key(purpose, user_id), &hash, ttl)
+        .ignore()
+        .query_async(&mut *conn)
+        .await?;
+
+    Ok(token)
+}
+
+/// Look up the user of a token without using it up
+pub async fn peek(
+    pool: &RedisPool,
+    purpose: Purpose,
+    token: &str,
+) -> Result<Option<i32>, HttpException> {
+    let user_id: Option<i32> = pool
+        .get()
+        .await?
+        .get(token_key(purpose, &sha256_hex(token)))
+        .await?;
+
+    Ok(user_id)
+}
+
+/// Invalidate the pending tokens of a user for every purpose
+pub async fn revoke_all(pool: &RedisPool, user_id: i32) -> Result<(), HttpException> {
+    let mut conn = pool.get().await?;
+    for purpose in Purpose::ALL {
+        let hash: Option<String> = conn.get_del(user_key(purpose, user_id)).await?;
+        if let Some(hash) = hash {
+            let _: () = conn.del(token_key(purpose, &hash)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Use up a token, returns the user it was issued for
+pub async fn consume(
+    pool: &RedisPool,
+    purpose: Purpose,
+    token: &str,
+) -> Result<Option<i32>, HttpException> {
+    let mut conn = pool.get().await?;
+    let user_id: Option<i32> = conn.get_del(token_key(purpose, &sha256_hex(token))).await?;
+    if let Some(user_id) = user_id {
+        let _: () = conn.del(user_key(purpose, user_id)).await?;
+    }
+
+    Ok(user_id)
+}
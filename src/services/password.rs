@@ -0,0 +1,38 @@
+//! Password verification with transparent upgrades
+//!
+//! Hashing itself lives in `entity::password` so that `user::ActiveModel::before_save` hashes
+//! every password that is set. Stored hashes using bcrypt or outdated Argon2id parameters are
+//! replaced after the next successful login.
+
+use crate::core::{config, exception::HttpException};
+use entity::user;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, IntoActiveModel, Set};
+
+/// Apply the Argon2id parameters from the config, must run before any password is hashed
+pub fn init(config: &config::Config) -> anyhow::Result<()> {
+    let (memory_kib, iterations, parallelism) = config.argon2_params();
+    entity::password::configure(memory_kib, iterations, parallelism)
+        .map_err(|err| anyhow::anyhow!("❌ Invalid argon2 parameters: {err}"))
+}
+
+/// Check the password of a user, rehashing it with the current parameters when it matches
+pub async fn verify<C>(db: &C, user: &user::Model, password: &str) -> Result<bool, HttpException>
+where
+    C: ConnectionTrait,
+{
+    if !entity::password::verify(password, &user.password) {
+        return Ok(false);
+    }
+
+    if entity::password::needs_rehash(&user.password) {
+        let mut user = user.clone().into_active_model();
+        // hashed by `before_save`
+        user.password = Set(password.to_string());
+        if let Err(err) = user.update(db).await {
+            // the login itself is still valid, try again next time
+            tracing::warn!(%err, "failed to upgrade password hash");
+        }
+    }
+
+    Ok(true)
+}
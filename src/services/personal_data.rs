@@ -0,0 +1,482 @@
+//! Personal data export and erasure
+//!
+//! An export is built in the background into a zip archive under `exports/{user_id}` and the
+//! user is notified over the socket once it can be downloaded, for `EXPORT_TTL` seconds.
+//! Erasure removes everything a user created and anonymizes the account, which is then purged
+//! with the other soft-deleted users.
+
+use crate::{
+    core::{
+        config,
+        exception::HttpException,
+        state::{AppState, RedisPool},
+    },
+    events, http_exception, http_exception_or,
+    services::{login_throttle, one_time_token, rbac, reaction, session, upload},
+    utils::random_token,
+};
+use bb8_redis::redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
+use entity::{
+    audit_log, comment, mfa_recovery_code, personal_access_token, post, post_reaction,
+    prelude::{
+        AuditLog, Comment, MfaRecoveryCode, PersonalAccessToken, Post, PostReaction, User,
+        UserFollow, UserIdentity, UserRole,
+    },
+    soft_delete::SoftDelete,
+    user, user_follow, user_identity, user_role,
+};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+pub const EXPORTS_DIRECTORY: &str = "exports";
+// upper bound of the time to build an export, the lock is released as soon as it is done
+const EXPORT_LOCK_TTL: u64 = 60 * 10;
+
+fn export_key(id: &str) -> String {
+    format!("{}:export:{id}", config::Config::global().app_auth_key())
+}
+
+fn export_lock_key(user_id: i32) -> String {
+    format!(
+        "{}:export_lock:{user_id}",
+        config::Config::global().app_auth_key()
+    )
+}
+
+fn user_exports_dir(user_id: i32) -> PathBuf {
+    Path::new(EXPORTS_DIRECTORY).join(user_id.to_string())
+}
+
+fn export_path(user_id: i32, id: &str) -> PathBuf {
+    user_exports_dir(user_id).join(format!("{id}.zip"))
+}
+
+/// Payload of the socket events about an export
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportNotice<'a> {
+    id: &'a str,
+    /// seconds the archive can be downloaded for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_in: Option<i64>,
+}
+
+/// A session as written to the export, without its refresh hash
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedSession {
+    id: String,
+    device_id: String,
+    user_agent: Option<String>,
+    ip: Option<String>,
+    created_at: i64,
+    last_seen: i64,
+}
+
+impl From<session::Session> for ExportedSession {
+    fn from(session: session::Session) -> Self {
+        Self {
+            id: session.id,
+            device_id: session.device_id,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            created_at: session.created_at,
+            last_seen: session.last_seen,
+        }
+    }
+}
+
+/// Start building an export of the data of a user in the background, returns its id.
+/// Only one export of a user is built at a time.
+pub async fn start_export(state: Arc<AppState>, user_id: i32) -> Result<String, HttpException> {
+    let options = SetOptions::default()
+        .conditional_set(ExistenceCheck::NX)
+        .with_expiration(SetExpiry::EX(EXPORT_LOCK_TTL));
+    let locked: Option<String> = state
+        .redis_pool
+        .get()
+        .await?
+        .set_options(export_lock_key(user_id), 1, options)
+        .await?;
+    if locked.is_none() {
+        http_exception!(TooManyRequestsException, "An export is already being built");
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let export_id = id.clone();
+    tokio::spawn(async move {
+        let result = build_export(&state, user_id, &export_id).await;
+        if let Err(err) = release_export_lock(&state.redis_pool, user_id).await {
+            tracing::error!(user_id, ?err, "failed to release the export lock");
+        }
+
+        match result {
+            Ok(()) => {
+                let notice = ExportNotice {
+                    id: &export_id,
+                    expires_in: Some(config::Config::global().export_ttl()),
+                };
+                events::emit_to_user(
+                    &state.io,
+                    &state.clients,
+                    user_id,
+                    events::EXPORT_READY,
+                    &notice,
+                );
+            }
+            Err(err) => {
+                tracing::error!(user_id, ?err, "failed to build the export");
+                let notice = ExportNotice {
+                    id: &export_id,
+                    expires_in: None,
+                };
+                events::emit_to_user(
+                    &state.io,
+                    &state.clients,
+                    user_id,
+                    events::EXPORT_FAILED,
+                    &notice,
+                );
+            }
+        }
+    });
+
+    Ok(id)
+}
+
+async fn release_export_lock(pool: &RedisPool, user_id: i32) -> Result<(), HttpException> {
+    let _: () = pool.get().await?.del(export_lock_key(user_id)).await?;
+
+    Ok(())
+}
+
+async fn build_export(state: &AppState, user_id: i32, id: &str) -> Result<(), HttpException> {
+    let user = http_exception_or!(
+        User::find_active_by_id(user_id).one(&state.db).await?,
+        NotFoundException,
+        format!("No user found with id {}", user_id)
+    );
+    // posts in the trash are still kept, so they are exported too
+    let posts = Post::find_with_deleted()
+        .filter(post::Column::UserId.eq(user_id))
+        .order_by_asc(post::Column::Id)
+        .all(&state.db)
+        .await?;
+    let comments = Comment::find()
+        .filter(comment::Column::UserId.eq(user_id))
+        .order_by_asc(comment::Column::Id)
+        .all(&state.db)
+        .await?;
+    let reactions = PostReaction::find()
+        .filter(post_reaction::Column::UserId.eq(user_id))
+        .order_by_asc(post_reaction::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+    let following = UserFollow::find()
+        .filter(user_follow::Column::FollowerId.eq(user_id))
+        .order_by_asc(user_follow::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+    let tokens = PersonalAccessToken::find()
+        .filter(personal_access_token::Column::UserId.eq(user_id))
+        .order_by_asc(personal_access_token::Column::Id)
+        .all(&state.db)
+        .await?;
+    let identities = UserIdentity::find()
+        .filter(user_identity::Column::UserId.eq(user_id))
+        .order_by_asc(user_identity::Column::Id)
+        .all(&state.db)
+        .await?;
+    let access = rbac::load_access(&state.db, user_id).await?;
+    let sessions: Vec<ExportedSession> = session::list(&state.redis_pool, user_id)
+        .await?
+        .into_iter()
+        .map(ExportedSession::from)
+        .collect();
+
+    let documents = vec![
+        ("profile.json", to_json(&user)?),
+        ("posts.json", to_json(&posts)?),
+        ("comments.json", to_json(&comments)?),
+        ("reactions.json", to_json(&reactions)?),
+        ("following.json", to_json(&following)?),
+        ("access.json", to_json(&access)?),
+        ("sessions.json", to_json(&sessions)?),
+        ("tokens.json", to_json(&tokens)?),
+        ("identities.json", to_json(&identities)?),
+    ];
+    let path = export_path(user_id, id);
+    let uploads = upload::user_dir(user_id);
+    tokio::task::spawn_blocking(move || write_archive(&path, documents, &uploads))
+        .await
+        .map_err(|err| HttpException::InternalServerErrorException(Some(err.to_string())))??;
+
+    let ttl = config::Config::global().export_ttl();
+    let _: () = state
+        .redis_pool
+        .get()
+        .await?
+        .set_ex(export_key(id), user_id, ttl as u64)
+        .await?;
+
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, HttpException> {
+    serde_json::to_vec_pretty(value)
+        .map_err(|err| HttpException::InternalServerErrorException(Some(err.to_string())))
+}
+
+fn write_archive(
+    path: &Path,
+    documents: Vec<(&'static str, Vec<u8>)>,
+    uploads: &Path,
+) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new(EXPORTS_DIRECTORY));
+    std::fs::create_dir_all(dir)?;
+    // written next to its final path and only moved there once complete
+    let mut zip = ZipWriter::new(tempfile::NamedTempFile::new_in(dir)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for (name, content) in documents {
+        zip.start_file(name, options)?;
+        zip.write_all(&content)?;
+    }
+    match std::fs::read_dir(uploads) {
+        Ok(entries) => {
+            for entry in entries {
+                let entry = entry?;
+                if !entry.file_type()?.is_file() {
+                    continue;
+                }
+                let name = format!("uploads/{}", entry.file_name().to_string_lossy());
+                zip.start_file(name, options)?;
+                io::copy(&mut std::fs::File::open(entry.path())?, &mut zip)?;
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+
+    zip.finish()?.persist(path).map_err(|err| err.error)?;
+
+    Ok(())
+}
+
+/// Path of a finished export of the user, `None` when there is none or it expired
+pub async fn export_file(
+    pool: &RedisPool,
+    user_id: i32,
+    id: &str,
+) -> Result<Option<PathBuf>, HttpException> {
+    let owner: Option<i32> = pool.get().await?.get(export_key(id)).await?;
+    if owner != Some(user_id) {
+        return Ok(None);
+    }
+
+    let path = export_path(user_id, id);
+    let exists = tokio::fs::try_exists(&path).await?;
+    Ok(exists.then_some(path))
+}
+
+/// Delete the archives whose download window has passed
+pub async fn remove_expired_exports() -> io::Result<()> {
+    let ttl = config::Config::global().export_ttl().max(0) as u64;
+    let max_age = Duration::from_secs(ttl);
+    tokio::task::spawn_blocking(move || {
+        let users = match std::fs::read_dir(EXPORTS_DIRECTORY) {
+            Ok(users) => users,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        let now = SystemTime::now();
+        for dir in users {
+            let dir = dir?;
+            if !dir.file_type()?.is_dir() {
+                continue;
+            }
+            for file in std::fs::read_dir(dir.path())? {
+                let file = file?;
+                let modified = file.metadata()?.modified()?;
+                if now.duration_since(modified).unwrap_or_default() > max_age {
+                    std::fs::remove_file(file.path())?;
+                }
+            }
+        }
+
+        Ok(())
+    })
+    .await?
+}
+
+/// Erase the personal data of a user. Posts, comments with their replies, reactions, follows,
+/// access tokens, linked identities, uploads, exports and the state kept in redis are removed, the
+/// account itself is anonymized, disabled and soft deleted. The audit trail is kept without the
+/// addresses the user acted from.
+pub async fn erase(state: &AppState, user_id: i32) -> Result<(), HttpException> {
+    let user = http_exception_or!(
+        User::find_with_deleted_by_id(user_id)
+            .one(&state.db)
+            .await?,
+        NotFoundException,
+        format!("No user found with id {}", user_id)
+    );
+
+    let txn = state.db.begin().await?;
+    let erased = erase_records(&txn, user).await?;
+    txn.commit().await?;
+
+    forget(state, erased).await
+}
+
+/// What is left to clean up outside the database once an erasure is committed
+pub struct Erased {
+    user_id: i32,
+    email: String,
+    /// posts whose cached reaction counts included the user
+    reacted: Vec<i32>,
+}
+
+/// The database part of `erase`, for callers that record it in their own transaction
+pub async fn erase_records<C>(db: &C, user: user::Model) -> Result<Erased, HttpException>
+where
+    C: ConnectionTrait,
+{
+    let user_id = user.id;
+    let email = user.email.clone();
+    let now = chrono::Utc::now();
+
+    Post::delete_many()
+        .filter(post::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    Comment::delete_many()
+        .filter(comment::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    let reacted: Vec<i32> = PostReaction::find()
+        .select_only()
+        .column(post_reaction::Column::PostId)
+        .distinct()
+        .filter(post_reaction::Column::UserId.eq(user_id))
+        .into_tuple()
+        .all(db)
+        .await?;
+    PostReaction::delete_many()
+        .filter(post_reaction::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    UserFollow::delete_many()
+        .filter(
+            Condition::any()
+                .add(user_follow::Column::FollowerId.eq(user_id))
+                .add(user_follow::Column::FollowedId.eq(user_id)),
+        )
+        .exec(db)
+        .await?;
+    PersonalAccessToken::delete_many()
+        .filter(personal_access_token::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    MfaRecoveryCode::delete_many()
+        .filter(mfa_recovery_code::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    UserIdentity::delete_many()
+        .filter(user_identity::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    UserRole::delete_many()
+        .filter(user_role::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    AuditLog::update_many()
+        .col_expr(audit_log::Column::Ip, Expr::value(None::<String>))
+        .filter(audit_log::Column::ActorId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    let mut user: user::ActiveModel = user.into_active_model();
+    user.name = Set(format!("erased-{user_id}"));
+    user.email = Set(format!("erased-{user_id}@invalid"));
+    // hashed by `before_save`, nobody knows it
+    user.password = Set(random_token(32));
+    user.email_verified_at = Set(None);
+    user.totp_secret = Set(None);
+    user.totp_enabled_at = Set(None);
+    user.disabled_at = Set(Some(now));
+    user.deleted_at = Set(Some(now));
+    user.update(db).await?;
+
+    Ok(Erased {
+        user_id,
+        email,
+        reacted,
+    })
+}
+
+/// The part of `erase` outside the database: sessions, tokens, caches and files
+pub async fn forget(state: &AppState, erased: Erased) -> Result<(), HttpException> {
+    let user_id = erased.user_id;
+    session::terminate_all(state, user_id).await?;
+    one_time_token::revoke_all(&state.redis_pool, user_id).await?;
+    login_throttle::forget(&state.redis_pool, &erased.email).await?;
+    reaction::forget_counts(&state.redis_pool, &erased.reacted).await?;
+    let _: () = state
+        .redis_pool
+        .get()
+        .await?
+        .del(export_lock_key(user_id))
+        .await?;
+    remove_dir(&upload::user_dir(user_id)).await?;
+    remove_dir(&user_exports_dir(user_id)).await?;
+
+    tracing::info!(user_id, "erased personal data");
+    Ok(())
+}
+
+async fn remove_dir(path: &Path) -> io::Result<()> {
+    match tokio::fs::remove_dir_all(path).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_write_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let uploads = dir.path().join("uploads");
+        std::fs::create_dir(&uploads).unwrap();
+        std::fs::write(uploads.join("avatar.png"), b"png").unwrap();
+        let path = dir.path().join("exports").join("export.zip");
+
+        write_archive(&path, vec![("profile.json", b"{}".to_vec())], &uploads).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut profile = String::new();
+        archive
+            .by_name("profile.json")
+            .unwrap()
+            .read_to_string(&mut profile)
+            .unwrap();
+        assert_eq!(profile, "{}");
+        assert!(archive.by_name("uploads/avatar.png").is_ok());
+    }
+}
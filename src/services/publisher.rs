@@ -0,0 +1,172 @@
+//! Publication of posts
+//!
+//! A post is published right away or scheduled for a later `published_at`. Every
+//! `PUBLISH_INTERVAL` the scheduled posts whose time has come are published, unless their
+//! author is disabled or deleted. Whenever a public post goes out, the followers of its author
+//! get a `post:published` socket event.
+
+use crate::{
+    core::{config, exception::HttpException, state::AppState},
+    events,
+};
+use chrono::{DateTime, Utc};
+use entity::{
+    post,
+    prelude::{Post, UserFollow},
+    sea_orm_active_enums::{PostStatus, Visibility},
+    user, user_follow,
+};
+use sea_orm::{
+    sea_query::{Expr, Query as SelectQuery},
+    ColumnTrait, EntityTrait, QueryFilter, QuerySelect,
+};
+use serde::Serialize;
+use std::{sync::Arc, time::Duration};
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+/// Payload of the `post:published` socket event
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PublishedNotice<'a> {
+    id: i32,
+    user_id: i32,
+    title: &'a str,
+    published_at: Option<DateTime<Utc>>,
+}
+
+/// `published_at` of a post moving to `status`, `current` is the one it has so far
+pub fn published_at(
+    status: PostStatus,
+    requested: Option<DateTime<Utc>>,
+    current: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, HttpException> {
+    match status {
+        PostStatus::Draft => Ok(None),
+        PostStatus::Scheduled => match requested {
+            Some(at) if at > now => Ok(Some(at)),
+            _ => Err(HttpException::BadRequestException(Some(
+                "A scheduled post needs a publication time in the future".to_string(),
+            ))),
+        },
+        // a post published before keeps its date, one published ahead of schedule goes out now
+        PostStatus::Published => Ok(current.filter(|at| *at <= now).or(Some(now))),
+        PostStatus::Archived => Ok(current),
+    }
+}
+
+/// Tell the followers of the author that a public post went out, unlisted posts are only found
+/// through their link. The post is out either way, a failure is only logged.
+pub async fn notify_published(state: &AppState, post: &post::Model) {
+    if post.status != PostStatus::Published || post.visibility != Visibility::Public {
+        return;
+    }
+
+    let followers: Vec<i32> = match UserFollow::find()
+        .select_only()
+        .column(user_follow::Column::FollowerId)
+        .filter(user_follow::Column::FollowedId.eq(post.user_id))
+        .into_tuple()
+        .all(&state.db)
+        .await
+    {
+        Ok(followers) => followers,
+        Err(err) => {
+            tracing::error!(post_id = post.id, ?err, "failed to notify the followers");
+            return;
+        }
+    };
+    let notice = PublishedNotice {
+        id: post.id,
+        user_id: post.user_id,
+        title: &post.title,
+        published_at: post.published_at,
+    };
+    for follower in followers {
+        events::emit_to_user(
+            &state.io,
+            &state.clients,
+            follower,
+            events::POST_PUBLISHED,
+            &notice,
+        );
+    }
+}
+
+/// Run the scheduler in the background until the runtime shuts down
+pub fn spawn(state: Arc<AppState>) -> JoinHandle<()> {
+    let period = Duration::from_secs(config::Config::global().publish_interval().max(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(err) = run(&state).await {
+                tracing::error!(?err, "failed to publish scheduled posts");
+            }
+        }
+    })
+}
+
+/// Publish the scheduled posts that are due. Posts of disabled or deleted authors wait until
+/// the author is back.
+pub async fn run(state: &AppState) -> Result<(), HttpException> {
+    let now = Utc::now();
+    let active_authors = SelectQuery::select()
+        .column((user::Entity, user::Column::Id))
+        .from(user::Entity)
+        .and_where(Expr::col((user::Entity, user::Column::DeletedAt)).is_null())
+        .and_where(Expr::col((user::Entity, user::Column::DisabledAt)).is_null())
+        .to_owned();
+    // claimed by a single update, so several instances never publish a post twice
+    let posts = Post::update_many()
+        .col_expr(post::Column::Status, Expr::cust("'published'::post_status"))
+        .col_expr(post::Column::UpdatedAt, Expr::value(now))
+        .filter(post::Column::Status.eq(PostStatus::Scheduled))
+        .filter(post::Column::PublishedAt.lte(now))
+        .filter(post::Column::DeletedAt.is_null())
+        .filter(post::Column::UserId.in_subquery(active_authors))
+        .exec_with_returning(&state.db)
+        .await?;
+    if !posts.is_empty() {
+        tracing::info!(posts = posts.len(), "published scheduled posts");
+    }
+
+    for post in &posts {
+        notify_published(state, post).await;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_published_at() {
+        let now = Utc::now();
+        let hour = chrono::Duration::hours(1);
+
+        assert_eq!(
+            published_at(PostStatus::Draft, None, Some(now - hour), now).unwrap(),
+            None
+        );
+        assert_eq!(
+            published_at(PostStatus::Scheduled, Some(now + hour), None, now).unwrap(),
+            Some(now + hour)
+        );
+        assert!(published_at(PostStatus::Scheduled, Some(now - hour), None, now).is_err());
+        assert!(published_at(PostStatus::Scheduled, None, None, now).is_err());
+
+        // published before, published early
+        assert_eq!(
+            published_at(PostStatus::Published, None, Some(now - hour), now).unwrap(),
+            Some(now - hour)
+        );
+        assert_eq!(
+            published_at(PostStatus::Published, None, Some(now + hour), now).unwrap(),
+            Some(now)
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Purge job for soft-deleted rows
+//!
+//! Users and posts soft deleted longer than `SOFT_DELETE_RETENTION` ago are deleted for good
+//! every `PURGE_INTERVAL`. Posts of a purged user go with it through the foreign key.
+//! Personal data exports are removed once their download window has passed.
+
+use crate::{core::config, services::personal_data};
+use entity::{post, prelude::Post, prelude::User, user};
+use sea_orm::{ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use std::time::Duration;
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+/// Run the purge job in the background until the runtime shuts down
+pub fn spawn(db: DatabaseConnection) -> JoinHandle<()> {
+    let period = Duration::from_secs(config::Config::global().purge_interval().max(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(err) = run(&db).await {
+                tracing::error!(%err, "failed to purge soft-deleted rows");
+            }
+            if let Err(err) = personal_data::remove_expired_exports().await {
+                tracing::error!(%err, "failed to remove expired exports");
+            }
+        }
+    })
+}
+
+/// Permanently delete the rows whose retention window has passed
+pub async fn run<C>(db: &C) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let retention = config::Config::global().soft_delete_retention();
+    let cutoff = chrono::Utc::now() - chrono::Duration::seconds(retention);
+
+    let posts = Post::delete_many()
+        .filter(post::Column::DeletedAt.lt(cutoff))
+        .exec(db)
+        .await?
+        .rows_affected;
+    let users = User::delete_many()
+        .filter(user::Column::DeletedAt.lt(cutoff))
+        .exec(db)
+        .await?
+        .rows_affected;
+    if posts > 0 || users > 0 {
+        tracing::info!(posts, users, "purged soft-deleted rows");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+//! Roles and permissions, loaded when tokens are issued and carried in `Claims`
+
+use entity::{
+    permission,
+    prelude::{Permission, Role, RolePermission, UserRole},
+    role, role_permission, user_role,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+};
+use serde::{Deserialize, Serialize};
+
+pub const ROLE_ADMIN: &str = "admin";
+pub const ROLE_USER: &str = "user";
+
+pub mod permissions {
+    pub const USERS_READ: &str = "users:read";
+    pub const USERS_WRITE: &str = "users:write";
+    pub const USERS_DELETE: &str = "users:delete";
+    pub const AUDIT_LOGS_READ: &str = "audit_logs:read";
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Access {
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+impl Access {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+}
+
+/// Load the roles of a user and the permissions they grant
+pub async fn load_access<C>(db: &C, user_id: i32) -> Result<Access, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let role_ids: Vec<i32> = UserRole::find()
+        .filter(user_role::Column::UserId.eq(user_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|user_role| user_role.role_id)
+        .collect();
+    if role_ids.is_empty() {
+        return Ok(Access::default());
+    }
+
+    let roles = Role::find()
+        .filter(role::Column::Id.is_in(role_ids.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|role| role.name)
+        .collect();
+
+    let permission_ids: Vec<i32> = RolePermission::find()
+        .filter(role_permission::Column::RoleId.is_in(role_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|role_permission| role_permission.permission_id)
+        .collect();
+    let mut permissions: Vec<String> = Permission::find()
+        .filter(permission::Column::Id.is_in(permission_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|permission| permission.name)
+        .collect();
+    permissions.sort();
+    permissions.dedup();
+
+    Ok(Access { roles, permissions })
+}
+
+/// Grant a role by name, granting a role twice is a no-op
+pub async fn assign_role<C>(db: &C, user_id: i32, role_name: &str) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let Some(role) = Role::find()
+        .filter(role::Column::Name.eq(role_name))
+        .one(db)
+        .await?
+    else {
+        return Err(DbErr::RecordNotFound(format!("role {role_name}")));
+    };
+
+    let exists = UserRole::find_by_id((user_id, role.id)).one(db).await?;
+    if exists.is_none() {
+        user_role::ActiveModel {
+            user_id: Set(user_id),
+            role_id: Set(role.id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+//! Reactions to posts
+//!
+//! A user can react to a post once with each reaction. How many of each reaction a post got
+//! is cached in redis for `COUNTS_TTL` seconds and dropped whenever one is added or removed.
+//! The cache is only filled when empty, counts read before a change that land after it was
+//! dropped are stale for `COUNTS_TTL` at most.
+
+use crate::core::{config, exception::HttpException, state::RedisPool};
+use bb8_redis::redis::{self, AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
+use entity::{post_reaction, prelude::PostReaction, sea_orm_active_enums::Reaction};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect, Set,
+};
+use std::collections::{BTreeMap, HashMap};
+
+const COUNTS_TTL: u64 = 60;
+
+/// Number of users who reacted to a post with each reaction, reactions nobody used are left out
+pub type ReactionCounts = BTreeMap<Reaction, i64>;
+
+fn counts_key(post_id: i32) -> String {
+    format!(
+        "{}:post_reactions:{post_id}",
+        config::Config::global().app_auth_key()
+    )
+}
+
+/// Counts of each of the posts, read from the cache and computed for the posts it misses
+pub async fn counts<C>(
+    db: &C,
+    pool: &RedisPool,
+    post_ids: &[i32],
+) -> Result<HashMap<i32, ReactionCounts>, HttpException>
+where
+    C: ConnectionTrait,
+{
+    let mut counts: HashMap<i32, ReactionCounts> = HashMap::new();
+    if post_ids.is_empty() {
+        return Ok(counts);
+    }
+
+    let mut conn = pool.get().await?;
+    let keys: Vec<String> = post_ids
+        .iter()
+        .map(|post_id| counts_key(*post_id))
+        .collect();
+    let cached: Vec<Option<String>> = conn.mget(keys).await?;
+    let mut missing = Vec::new();
+    for (post_id, json) in post_ids.iter().zip(cached) {
+        match json.and_then(|json| serde_json::from_str(&json).ok()) {
+            Some(post_counts) => {
+                counts.insert(*post_id, post_counts);
+            }
+            None => missing.push(*post_id),
+        }
+    }
+    if missing.is_empty() {
+        return Ok(counts);
+    }
+
+    let rows: Vec<(i32, Reaction, i64)> = PostReaction::find()
+        .select_only()
+        .column(post_reaction::Column::PostId)
+        .column(post_reaction::Column::Reaction)
+        .column_as(Expr::col(post_reaction::Column::UserId).count(), "count")
+        .filter(post_reaction::Column::PostId.is_in(missing.clone()))
+        .group_by(post_reaction::Column::PostId)
+        .group_by(post_reaction::Column::Reaction)
+        .into_tuple()
+        .all(db)
+        .await?;
+    let mut fresh: HashMap<i32, ReactionCounts> = missing
+        .iter()
+        .map(|post_id| (*post_id, ReactionCounts::new()))
+        .collect();
+    for (post_id, reaction, count) in rows {
+        fresh.entry(post_id).or_default().insert(reaction, count);
+    }
+
+    let mut pipe = redis::pipe();
+    for (post_id, post_counts) in &fresh {
+        let json = serde_json::to_string(post_counts).unwrap_or_default();
+        let options = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .with_expiration(SetExpiry::EX(COUNTS_TTL));
+        pipe.set_options(counts_key(*post_id), json, options)
+            .ignore();
+    }
+    let _: () = pipe.query_async(&mut *conn).await?;
+    counts.extend(fresh);
+
+    Ok(counts)
+}
+
+/// Drop the cached counts of the posts once their reactions changed
+pub async fn forget_counts(pool: &RedisPool, post_ids: &[i32]) -> Result<(), HttpException> {
+    if post_ids.is_empty() {
+        return Ok(());
+    }
+
+    let keys: Vec<String> = post_ids
+        .iter()
+        .map(|post_id| counts_key(*post_id))
+        .collect();
+    let _: () = pool.get().await?.del(keys).await?;
+
+    Ok(())
+}
+
+/// React to a post, reacting twice the same way changes nothing
+pub async fn add<C>(
+    db: &C,
+    pool: &RedisPool,
+    post_id: i32,
+    user_id: i32,
+    reaction: Reaction,
+) -> Result<(), HttpException>
+where
+    C: ConnectionTrait,
+{
+    // `insert` of the entity skips `before_save`
+    PostReaction::insert(post_reaction::ActiveModel {
+        post_id: Set(post_id),
+        user_id: Set(user_id),
+        reaction: Set(reaction),
+        created_at: Set(Some(chrono::Utc::now())),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            post_reaction::Column::PostId,
+            post_reaction::Column::UserId,
+            post_reaction::Column::Reaction,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .do_nothing()
+    .exec(db)
+    .await?;
+
+    forget_counts(pool, &[post_id]).await
+}
+
+/// Take back a reaction to a post
+pub async fn remove<C>(
+    db: &C,
+    pool: &RedisPool,
+    post_id: i32,
+    user_id: i32,
+    reaction: Reaction,
+) -> Result<(), HttpException>
+where
+    C: ConnectionTrait,
+{
+    PostReaction::delete_many()
+        .filter(post_reaction::Column::PostId.eq(post_id))
+        .filter(post_reaction::Column::UserId.eq(user_id))
+        .filter(post_reaction::Column::Reaction.eq(reaction))
+        .exec(db)
+        .await?;
+
+    forget_counts(pool, &[post_id]).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_json() {
+        let counts = ReactionCounts::from([(Reaction::Like, 3), (Reaction::Wow, 1)]);
+        let json = serde_json::to_string(&counts).unwrap();
+        assert_eq!(json, r#"{"like":3,"wow":1}"#);
+        assert_eq!(
+            serde_json::from_str::<ReactionCounts>(&json).unwrap(),
+            counts
+        );
+    }
+}
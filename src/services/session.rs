@@ -0,0 +1,364 @@
+//! Redis backed login sessions
+//!
+//! Every login creates one session per user and device. The session keeps the hash of the
+//! current refresh token, and each refresh token can be used once. Presenting a token that
+//! was already rotated means it leaked, so the whole token family (the session) is revoked.
+
+use crate::{
+    core::{
+        config,
+        exception::HttpException,
+        state::{AppState, RedisPool},
+    },
+    events,
+    extractors::ClientInfo,
+    guards::jwt_encode,
+    http_exception, http_exception_or,
+    services::rbac,
+    utils::{random_token, sha256_hex, unix_now},
+};
+use bb8_redis::redis::{self, AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// how often `last_seen` is written back, in seconds
+const TOUCH_INTERVAL: i64 = 60;
+// a refresh racing with another write to its session reads the session again this many times
+const ROTATE_ATTEMPTS: usize = 3;
+
+// Replaces the session (KEYS[1]) only if it is still the value the rotation was computed
+// from (ARGV[1]), so the same refresh token can not be used twice and a revoked session is
+// not brought back. The new refresh hash (KEYS[2]) points at the session like the old ones.
+const ROTATE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) ~= ARGV[1] then
+    return 0
+end
+redis.call('SET', KEYS[1], ARGV[2], 'EX', ARGV[4])
+redis.call('SET', KEYS[2], ARGV[3], 'EX', ARGV[4])
+redis.call('SADD', KEYS[3], ARGV[3])
+redis.call('EXPIRE', KEYS[3], ARGV[4])
+return 1
+"#;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub user_id: i32,
+    pub device_id: String,
+    pub refresh_hash: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: i64,
+    pub last_seen: i64,
+    /// admin acting as the user
+    #[serde(default)]
+    pub impersonator_id: Option<i32>,
+}
+
+/// Credentials handed out to the client after login or refresh
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// access token lifetime in seconds
+    pub expires_in: i64,
+}
+
+fn session_key(sid: &str) -> String {
+    format!("{}:session:{sid}", config::Config::global().app_auth_key())
+}
+
+fn user_sessions_key(user_id: i32) -> String {
+    format!(
+        "{}:user_sessions:{user_id}",
+        config::Config::global().app_auth_key()
+    )
+}
+
+fn refresh_key(hash: &str) -> String {
+    format!("{}:refresh:{hash}", config::Config::global().app_auth_key())
+}
+
+/// Start a new session and issue its first token pair.
+/// An existing session of the same user on the same device is replaced.
+pub async fn create(
+    state: &AppState,
+    user_id: i32,
+    client: &ClientInfo,
+) -> Result<(Session, TokenPair), HttpException> {
+    let pool = &state.redis_pool;
+    let device_id = client
+        .device_id
+        .clone()
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let replaced: Vec<Session> = list(pool, user_id)
+        .await?
+        .into_iter()
+        .filter(|session| session.device_id == device_id && session.impersonator_id.is_none())
+        .collect();
+    terminate(state, &replaced).await?;
+
+    let sid = Uuid::new_v4().to_string();
+    start(state, sid, user_id, device_id, None, client).await
+}
+
+/// Start a session for `user_id` on behalf of the admin `impersonator_id`. The session id
+/// `sid` is chosen by the caller so the impersonation can be audited before it starts.
+/// It gets a device of its own so the sessions of the user are left alone, and it cannot be
+/// refreshed so it ends with its first access token.
+pub async fn impersonate(
+    state: &AppState,
+    sid: String,
+    user_id: i32,
+    impersonator_id: i32,
+    client: &ClientInfo,
+) -> Result<(Session, TokenPair), HttpException> {
+    let device_id = format!("impersonation-{}", Uuid::new_v4());
+    start(
+        state,
+        sid,
+        user_id,
+        device_id,
+        Some(impersonator_id),
+        client,
+    )
+    .await
+}
+
+async fn start(
+    state: &AppState,
+    sid: String,
+    user_id: i32,
+    device_id: String,
+    impersonator_id: Option<i32>,
+    client: &ClientInfo,
+) -> Result<(Session, TokenPair), HttpException> {
+    let pool = &state.redis_pool;
+    let now = unix_now();
+    let refresh_token = random_token(32);
+    let session = Session {
+        id: sid,
+        user_id,
+        device_id,
+        refresh_hash: sha256_hex(&refresh_token),
+        user_agent: client.user_agent.clone(),
+        ip: client.ip.clone(),
+        created_at: now,
+        last_seen: now,
+        impersonator_id,
+    };
+    save(pool, &session).await?;
+
+    let tokens = token_pair(state, &session, refresh_token).await?;
+    Ok((session, tokens))
+}
+
+/// Exchange a refresh token for a new token pair.
+/// Roles and permissions are reloaded so the new access token reflects their changes.
+pub async fn rotate(
+    state: &AppState,
+    refresh_token: &str,
+) -> Result<(Session, TokenPair), HttpException> {
+    let pool = &state.redis_pool;
+    let hash = sha256_hex(refresh_token);
+    let ttl = config::Config::global().refresh_token_ttl();
+
+    for _ in 0..ROTATE_ATTEMPTS {
+        let mut conn = pool.get().await?;
+        let sid: Option<String> = conn.get(refresh_key(&hash)).await?;
+        let sid = http_exception_or!(sid, UnauthorizedException, "Invalid refresh token");
+        let value: Option<String> = conn.get(session_key(&sid)).await?;
+        let (value, mut session) = http_exception_or!(
+            value.and_then(|v| {
+                serde_json::from_str::<Session>(&v)
+                    .ok()
+                    .map(|session| (v, session))
+            }),
+            UnauthorizedException,
+            "Session has been revoked"
+        );
+
+        if session.impersonator_id.is_some() {
+            http_exception!(
+                ForbiddenException,
+                "Impersonation sessions cannot be refreshed"
+            );
+        }
+        if session.refresh_hash != hash {
+            tracing::warn!(
+                user_id = session.user_id,
+                sid = %session.id,
+                "refresh token reuse detected, revoking session"
+            );
+            revoke(pool, &session).await?;
+            http_exception!(
+                UnauthorizedException,
+                "Refresh token reuse detected, session revoked"
+            );
+        }
+
+        let refresh_token = random_token(32);
+        session.refresh_hash = sha256_hex(&refresh_token);
+        session.last_seen = unix_now();
+        let swapped: bool = redis::Script::new(ROTATE_SCRIPT)
+            .key(session_key(&session.id))
+            .key(refresh_key(&session.refresh_hash))
+            .key(user_sessions_key(session.user_id))
+            .arg(value)
+            .arg(to_json(&session)?)
+            .arg(&session.id)
+            .arg(ttl)
+            .invoke_async(&mut *conn)
+            .await?;
+        if swapped {
+            let tokens = token_pair(state, &session, refresh_token).await?;
+            return Ok((session, tokens));
+        }
+    }
+
+    http_exception!(
+        ConflictException,
+        "Session changed during refresh, try again"
+    );
+}
+
+pub async fn find(pool: &RedisPool, sid: &str) -> Result<Option<Session>, HttpException> {
+    let value: Option<String> = pool.get().await?.get(session_key(sid)).await?;
+    Ok(value.and_then(|v| serde_json::from_str(&v).ok()))
+}
+
+/// Whether the session behind an access token is still alive
+pub async fn is_active(pool: &RedisPool, sid: &str) -> Result<bool, HttpException> {
+    let exists: bool = pool.get().await?.exists(session_key(sid)).await?;
+    Ok(exists)
+}
+
+/// Record activity on a session, writes are throttled to one per `TOUCH_INTERVAL`
+pub async fn touch(pool: &RedisPool, session: &mut Session) -> Result<(), HttpException> {
+    let now = unix_now();
+    if now - session.last_seen < TOUCH_INTERVAL {
+        return Ok(());
+    }
+    session.last_seen = now;
+
+    let value = to_json(session)?;
+    // a session revoked in the meantime must not come back
+    let options = SetOptions::default()
+        .conditional_set(ExistenceCheck::XX)
+        .with_expiration(SetExpiry::KEEPTTL);
+    let _: Option<String> = pool
+        .get()
+        .await?
+        .set_options(session_key(&session.id), value, options)
+        .await?;
+
+    Ok(())
+}
+
+/// All live sessions of a user, stale index entries are cleaned up on the way
+pub async fn list(pool: &RedisPool, user_id: i32) -> Result<Vec<Session>, HttpException> {
+    let mut conn = pool.get().await?;
+    let sids: Vec<String> = conn.smembers(user_sessions_key(user_id)).await?;
+
+    let mut sessions = Vec::with_capacity(sids.len());
+    for sid in sids {
+        let value: Option<String> = conn.get(session_key(&sid)).await?;
+        match value.and_then(|v| serde_json::from_str::<Session>(&v).ok()) {
+            Some(session) => sessions.push(session),
+            None => {
+                let _: () = conn.srem(user_sessions_key(user_id), &sid).await?;
+            }
+        }
+    }
+    sessions.sort_by_key(|session| session.created_at);
+
+    Ok(sessions)
+}
+
+pub async fn revoke(pool: &RedisPool, session: &Session) -> Result<(), HttpException> {
+    let mut conn = pool.get().await?;
+    let _: () = redis::pipe()
+        .atomic()
+        .del(session_key(&session.id))
+        .ignore()
+        .srem(user_sessions_key(session.user_id), &session.id)
+        .ignore()
+        .query_async(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Revoke sessions and disconnect the sockets opened with them
+pub async fn terminate(state: &AppState, sessions: &[Session]) -> Result<(), HttpException> {
+    for session in sessions {
+        revoke(&state.redis_pool, session).await?;
+        events::disconnect_session(&state.io, &state.clients, session.user_id, &session.id);
+    }
+
+    Ok(())
+}
+
+/// Terminate every session of a user, returns the terminated sessions
+pub async fn terminate_all(state: &AppState, user_id: i32) -> Result<Vec<Session>, HttpException> {
+    let sessions = list(&state.redis_pool, user_id).await?;
+    terminate(state, &sessions).await?;
+
+    Ok(sessions)
+}
+
+async fn save(pool: &RedisPool, session: &Session) -> Result<(), HttpException> {
+    let config = config::Config::global();
+    // impersonation sessions are not refreshed and end with their access token
+    let ttl = match session.impersonator_id {
+        Some(_) => config.access_token_ttl(),
+        None => config.refresh_token_ttl(),
+    };
+    let value = to_json(session)?;
+
+    let mut conn = pool.get().await?;
+    // rotated refresh hashes keep pointing at the session so a replay can be detected
+    let _: () = redis::pipe()
+        .atomic()
+        .set_ex(session_key(&session.id), value, ttl as u64)
+        .ignore()
+        .set_ex(refresh_key(&session.refresh_hash), &session.id, ttl as u64)
+        .ignore()
+        .sadd(user_sessions_key(session.user_id), &session.id)
+        .ignore()
+        .expire(user_sessions_key(session.user_id), ttl)
+        .ignore()
+        .query_async(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+fn to_json(session: &Session) -> Result<String, HttpException> {
+    serde_json::to_string(session)
+        .map_err(|err| HttpException::InternalServerErrorException(Some(err.to_string())))
+}
+
+async fn token_pair(
+    state: &AppState,
+    session: &Session,
+    refresh_token: String,
+) -> Result<TokenPair, HttpException> {
+    let config = config::Config::global();
+    let access = rbac::load_access(&state.db, session.user_id).await?;
+    let access_token = jwt_encode(
+        session.user_id,
+        &session.id,
+        access,
+        session.impersonator_id,
+    )
+    .map_err(|err| HttpException::InternalServerErrorException(Some(err.to_string())))?;
+
+    Ok(TokenPair {
+        access_token,
+        refresh_token,
+        expires_in: config.access_token_ttl(),
+    })
+}
@@ -0,0 +1,173 @@
+//! Tags of posts
+//!
+//! Tags are shared by name between all posts. They are created the first time a post uses
+//! them and kept when no post does anymore.
+
+use entity::{
+    post, post_tag,
+    prelude::{Post, PostTag, Tag},
+    soft_delete::SoftDelete,
+    tag,
+};
+use sea_orm::{
+    sea_query::{Expr, OnConflict, Query, SelectStatement},
+    ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, FromQueryResult, JoinType,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub const MAX_TAGS: usize = 10;
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// How many posts carry a tag
+#[derive(Debug, Serialize, FromQueryResult)]
+pub struct TagUsage {
+    pub name: String,
+    pub count: i64,
+}
+
+/// Trimmed and lowercase, without empty names and duplicates, in the given order
+pub fn normalize<S: AsRef<str>>(names: &[S]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(names.len());
+    for name in names {
+        let name = name.as_ref().trim().to_lowercase();
+        if !name.is_empty() && !normalized.contains(&name) {
+            normalized.push(name);
+        }
+    }
+    normalized
+}
+
+/// Letters, digits, `-` and `_` once normalized
+pub fn is_valid(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty()
+        && name.chars().count() <= MAX_TAG_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Replace the tags of a post, returns the names it now carries
+pub async fn set_post_tags<C>(db: &C, post_id: i32, names: &[String]) -> Result<Vec<String>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let names = normalize(names);
+    PostTag::delete_many()
+        .filter(post_tag::Column::PostId.eq(post_id))
+        .exec(db)
+        .await?;
+    if names.is_empty() {
+        return Ok(names);
+    }
+
+    // `insert_many` skips `before_save`
+    let now = chrono::Utc::now();
+    Tag::insert_many(names.iter().map(|name| tag::ActiveModel {
+        name: Set(name.clone()),
+        created_at: Set(Some(now)),
+        ..Default::default()
+    }))
+    .on_conflict(
+        OnConflict::column(tag::Column::Name)
+            .do_nothing()
+            .to_owned(),
+    )
+    .do_nothing()
+    .exec(db)
+    .await?;
+
+    let tags = Tag::find()
+        .filter(tag::Column::Name.is_in(names.clone()))
+        .all(db)
+        .await?;
+    PostTag::insert_many(tags.iter().map(|tag| post_tag::ActiveModel {
+        post_id: Set(post_id),
+        tag_id: Set(tag.id),
+    }))
+    .exec(db)
+    .await?;
+
+    let mut names = names;
+    names.sort();
+    Ok(names)
+}
+
+/// Tag names of each of the posts, sorted by name
+pub async fn names_by_post<C>(db: &C, post_ids: &[i32]) -> Result<HashMap<i32, Vec<String>>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut names: HashMap<i32, Vec<String>> = HashMap::new();
+    if post_ids.is_empty() {
+        return Ok(names);
+    }
+
+    let rows: Vec<(i32, String)> = PostTag::find()
+        .select_only()
+        .column(post_tag::Column::PostId)
+        .column(tag::Column::Name)
+        .join(JoinType::InnerJoin, post_tag::Relation::Tag.def())
+        .filter(post_tag::Column::PostId.is_in(post_ids.iter().copied()))
+        .order_by_asc(tag::Column::Name)
+        .into_tuple()
+        .all(db)
+        .await?;
+    for (post_id, name) in rows {
+        names.entry(post_id).or_default().push(name);
+    }
+
+    Ok(names)
+}
+
+/// Ids of the posts carrying the tag, to filter posts with
+pub fn tagged(name: &str) -> SelectStatement {
+    Query::select()
+        .column((post_tag::Entity, post_tag::Column::PostId))
+        .from(post_tag::Entity)
+        .inner_join(
+            tag::Entity,
+            Expr::col((tag::Entity, tag::Column::Id))
+                .equals((post_tag::Entity, post_tag::Column::TagId)),
+        )
+        .and_where(Expr::col((tag::Entity, tag::Column::Name)).eq(name))
+        .to_owned()
+}
+
+/// Tags of the active posts matching `visible`, the most used first
+pub async fn usage<C>(db: &C, visible: Condition) -> Result<Vec<TagUsage>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    Post::find_active()
+        .filter(visible)
+        .select_only()
+        .column(tag::Column::Name)
+        .column_as(Expr::col((post::Entity, post::Column::Id)).count(), "count")
+        .join(JoinType::InnerJoin, post_tag::Relation::Post.def().rev())
+        .join(JoinType::InnerJoin, post_tag::Relation::Tag.def())
+        .group_by(tag::Column::Name)
+        .order_by_desc(Expr::col("count"))
+        .order_by_asc(tag::Column::Name)
+        .into_model::<TagUsage>()
+        .all(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(&[" Rust ", "web", "rust", "", "Web-Dev"]),
+            vec!["rust", "web", "web-dev"]
+        );
+        assert!(is_valid("web_dev-2"));
+        assert!(!is_valid("two words"));
+        assert!(!is_valid(&"a".repeat(MAX_TAG_LENGTH + 1)));
+    }
+}
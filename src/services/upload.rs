@@ -0,0 +1,31 @@
+//! Storage of uploaded files, every user gets a directory of their own
+
+use std::path::{Path, PathBuf};
+
+pub const UPLOADS_DIRECTORY: &str = "uploads";
+
+/// Directory holding the uploads of a user
+pub fn user_dir(user_id: i32) -> PathBuf {
+    Path::new(UPLOADS_DIRECTORY).join(user_id.to_string())
+}
+
+/// Path of an upload, only the last component of `name` is kept so it cannot leave the
+/// directory of the user
+pub fn file_path(user_id: i32, name: &str) -> Option<PathBuf> {
+    let name = Path::new(name).file_name()?;
+    Some(user_dir(user_id).join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_path_stays_in_user_dir() {
+        assert_eq!(
+            file_path(1, "../../etc/passwd"),
+            Some(PathBuf::from("uploads/1/passwd"))
+        );
+        assert_eq!(file_path(1, ".."), None);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod singleton;
 
+use rand::RngCore;
+use sha2::{Digest, Sha256};
 use tower_cookies::Cookie;
 
 /// get cookie value by name
@@ -9,3 +11,20 @@ pub fn get_cookie_value(cookies: &str, name: &str) -> Option<String> {
         .find(|cookie| cookie.name() == name)
         .map(|cookie| cookie.value().to_string())
 }
+
+/// generate a hex encoded random token of `len` bytes
+pub fn random_token(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// hex encoded sha256 digest, used to store tokens without keeping them in plain text
+pub fn sha256_hex(value: &str) -> String {
+    hex::encode(Sha256::digest(value.as_bytes()))
+}
+
+/// current unix timestamp in seconds
+pub fn unix_now() -> i64 {
+    time::OffsetDateTime::now_utc().unix_timestamp()
+}